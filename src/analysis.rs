@@ -1,194 +1,1243 @@
 use crate::{
+    custom::{raw_metrics, Aggregate, CustomMetric},
     error::{AppError, AppResult},
+    format::{scroll_text, NumberFormat, ValueMode},
+    history::{higher_is_worse, sparkline, HistoryEntry, SPARKLINE_RUNS},
+    logger,
     metrics::*,
+    thresholds::{Level, Thresholds},
 };
+use flate2::read::GzDecoder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ratatui::{prelude::*, widgets::*};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
 use walkdir::WalkDir;
 
-macro_rules! add_details {
-    ($rows:ident, $title:expr, $option:expr) => {{
-        use ratatui::style::{Color, Modifier, Style};
-
-        let header_style = Style::default()
-            .fg(Color::LightBlue)
-            .add_modifier(Modifier::BOLD);
-        let key_style = Style::default().fg(Color::Yellow);
-        let value_style = Style::default().fg(Color::Green);
-
-        if let Some(ref metric) = $option {
-            $rows.push(Row::new(vec![
-                Cell::from($title).style(header_style),
-                Cell::from(""),
-            ]));
-
-            for (key, value) in metric.details() {
-                $rows.push(Row::new(vec![
-                    Cell::from(key).style(key_style),
-                    Cell::from(value).style(value_style),
-                ]));
+pub fn summarize_directory(path: &Path, options: &SummaryOptions) -> AppResult<MetricsSummary> {
+    let scan = scan_directory(path, options, &AtomicBool::new(false))?;
+    Ok(MetricsSummary::summarize(&scan.data, options))
+}
+
+pub struct DirectoryScan {
+    pub data: Vec<JsonData>,
+    /// Metrics files left out because they matched an ignore rule.
+    pub ignored: usize,
+}
+
+/// The ignore files of one directory, in the order they take precedence across levels:
+/// `.ignore` rules anywhere beat `.gitignore` rules, which beat `.git/info/exclude`.
+const IGNORE_FILES: [&str; 3] = [".ignore", ".gitignore", ".git/info/exclude"];
+
+/// The ignore rules of the directories walked so far and their ancestors, matched the way the
+/// `ignore` crate's walker matches them, so that a single walk can both skip ignored metrics
+/// files and count them.
+struct IgnoreRules {
+    dirs: HashMap<PathBuf, [Option<Gitignore>; 3]>,
+    global: Gitignore,
+}
+
+impl IgnoreRules {
+    /// The rules of `root`'s ancestors and the global git excludes.
+    fn new(root: &Path) -> Self {
+        let mut rules = Self {
+            dirs: HashMap::new(),
+            global: Gitignore::global().0,
+        };
+        for dir in root.ancestors().skip(1) {
+            rules.load(dir);
+        }
+        rules
+    }
+
+    fn load(&mut self, dir: &Path) {
+        let matchers = IGNORE_FILES.map(|name| {
+            let file = dir.join(name);
+            file.is_file().then(|| {
+                let mut builder = GitignoreBuilder::new(dir);
+                builder.add(&file);
+                builder.build().unwrap_or_else(|_| Gitignore::empty())
+            })
+        });
+        if matchers.iter().any(Option::is_some) {
+            self.dirs.insert(dir.to_path_buf(), matchers);
+        }
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        (0..IGNORE_FILES.len())
+            .flat_map(|kind| {
+                path.ancestors()
+                    .skip(1)
+                    .filter_map(move |dir| self.dirs.get(dir)?[kind].as_ref())
+            })
+            .chain([&self.global])
+            .map(|matcher| matcher.matched(path, is_dir))
+            .find(|found| !found.is_none())
+            .is_some_and(|found| found.is_ignore())
+    }
+}
+
+/// Walks `path` once for metrics files, returning those an analysis reads, sorted, and the
+/// number left out by ignore rules (always 0 unless `options.respect_ignore`). Symlink loops
+/// are detected when following links and logged; like unreadable entries, they are skipped.
+fn walk_metrics_files(path: &Path, options: &SummaryOptions) -> (Vec<PathBuf>, usize) {
+    let mut rules = options.respect_ignore.then(|| IgnoreRules::new(path));
+    let mut ignored_dirs = HashSet::new();
+    let mut files = Vec::new();
+    let mut ignored = 0;
+    let entries = WalkDir::new(path)
+        .follow_links(options.follow_symlinks)
+        .into_iter()
+        .filter_map(|entry| {
+            entry
+                .inspect_err(|err| {
+                    if let Some(ancestor) = err.loop_ancestor() {
+                        logger::warn(format!(
+                            "Skipping symlink loop: {} points to its ancestor {}",
+                            err.path().unwrap_or(path).display(),
+                            ancestor.display()
+                        ));
+                    }
+                })
+                .ok()
+        });
+    for entry in entries {
+        let is_dir = entry.file_type().is_dir();
+        let is_ignored = entry.depth() > 0
+            && rules.as_ref().is_some_and(|rules| {
+                entry
+                    .path()
+                    .parent()
+                    .is_some_and(|parent| ignored_dirs.contains(parent))
+                    || rules.is_ignored(entry.path(), is_dir)
+            });
+        if is_dir {
+            if is_ignored {
+                ignored_dirs.insert(entry.into_path());
+            } else if let Some(rules) = &mut rules {
+                rules.load(entry.path());
+            }
+        } else if entry.file_type().is_file() && is_metrics_file(entry.path()) {
+            if is_ignored {
+                ignored += 1;
+            } else {
+                files.push(entry.into_path());
             }
-        } else {
-            $rows.push(Row::new(vec![
-                Cell::from($title).style(header_style),
-                Cell::from("N/A").style(value_style),
-            ]));
         }
-    }};
+    }
+    // Directory listing order depends on the file system; sort so every run parses (and, since
+    // an indexed parallel collect keeps input order, reports) files in the same path order.
+    files.sort_unstable();
+    (files, ignored)
 }
 
-pub fn analyze_directory(path: &Path) -> AppResult<Table<'static>> {
+/// Lists the metrics files under `path` that an analysis would parse, in sorted path order.
+pub fn collect_json_files(path: &Path, options: &SummaryOptions) -> Vec<PathBuf> {
+    walk_metrics_files(path, options).0
+}
+
+pub fn scan_directory(
+    path: &Path,
+    options: &SummaryOptions,
+    cancel: &AtomicBool,
+) -> AppResult<DirectoryScan> {
     if !path.is_dir() {
         return Err(AppError::AnalysisError(format!(
             "{} is not a directory",
             path.display()
         )));
     }
-    let json_files: Vec<_> = WalkDir::new(path)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| {
-            e.file_type().is_file() && e.path().extension() == Some(std::ffi::OsStr::new("json"))
-        })
-        .map(|e| e.path().to_string_lossy().to_string())
-        .collect();
-    let data: Vec<_> = json_files
+    let (json_files, ignored) = walk_metrics_files(path, options);
+    Ok(DirectoryScan {
+        data: parse_files(&json_files, options, cancel)?,
+        ignored,
+    })
+}
+
+/// Parses the given metrics files in parallel, keeping their order.
+fn parse_files(
+    files: &[PathBuf],
+    options: &SummaryOptions,
+    cancel: &AtomicBool,
+) -> AppResult<Vec<JsonData>> {
+    let data = files
         .par_iter()
-        .filter_map(|p| read_json_file(p))
-        .collect();
-    Ok(create_summary_table(MetricsSummary::summarize(data)).to_owned())
+        .map(|p| {
+            if cancel.load(Ordering::Relaxed) {
+                Ok(Vec::new())
+            } else {
+                read_metrics_file(p, options.strict)
+            }
+        })
+        .collect::<AppResult<Vec<_>>>();
+    if cancel.load(Ordering::Relaxed) {
+        return Err(AppError::Cancelled);
+    }
+    let mut data = merge_layouts(data?.into_iter().flatten().collect());
+    collapse_duplicate_spaces(&mut data, options);
+    Ok(data)
+}
+
+/// Reads the metrics file paths listed one per line in `list`, for `--files-from`. Blank lines
+/// and `#` comments are skipped, and listed paths that aren't files are logged and left out;
+/// a list left with no files is an error.
+pub fn read_file_list(list: &Path) -> AppResult<Vec<PathBuf>> {
+    let content = fs::read_to_string(list)
+        .map_err(|e| AppError::AnalysisError(format!("{}: {}", list.display(), e)))?;
+    let mut files = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = PathBuf::from(line);
+        if path.is_file() {
+            files.push(path);
+        } else {
+            logger::warn(format!(
+                "Skipping {} listed in {}: no such file",
+                path.display(),
+                list.display()
+            ));
+        }
+    }
+    if files.is_empty() {
+        return Err(AppError::AnalysisError(format!(
+            "{}: no files listed",
+            list.display()
+        )));
+    }
+    Ok(files)
+}
+
+/// Reads exactly the given metrics files, without walking any directory.
+pub fn scan_files(
+    files: &[PathBuf],
+    options: &SummaryOptions,
+    cancel: &AtomicBool,
+) -> AppResult<DirectoryScan> {
+    Ok(DirectoryScan {
+        data: parse_files(files, options, cancel)?,
+        ignored: 0,
+    })
+}
+
+/// Reads the metrics files under a directory, or inside an archive.
+pub fn read_path(
+    path: &Path,
+    options: &SummaryOptions,
+    cancel: &AtomicBool,
+) -> AppResult<DirectoryScan> {
+    if is_archive(path) {
+        let mut data = read_archive(path, options.strict, cancel)?;
+        collapse_duplicate_spaces(&mut data, options);
+        Ok(DirectoryScan { data, ignored: 0 })
+    } else {
+        scan_directory(path, options, cancel)
+    }
+}
+
+const ARCHIVE_SUFFIXES: [&str; 3] = [".tar", ".tar.gz", ".tgz"];
+
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file()
+        && path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy().to_lowercase();
+            ARCHIVE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        })
+}
+
+/// Parses the metrics files inside a `.tar`, `.tar.gz` or `.tgz` archive without extracting it.
+pub fn read_archive(path: &Path, strict: bool, cancel: &AtomicBool) -> AppResult<Vec<JsonData>> {
+    let archive_error =
+        |e: std::io::Error| AppError::AnalysisError(format!("{}: {}", path.display(), e));
+    let file = fs::File::open(path).map_err(archive_error)?;
+    let name = path.to_string_lossy().to_lowercase();
+    let reader: Box<dyn Read> = if name.ends_with(".tar") {
+        Box::new(file)
+    } else {
+        Box::new(GzDecoder::new(file))
+    };
+    let mut archive = tar::Archive::new(reader);
+    let mut data = Vec::new();
+    let mut skipped = 0;
+    for entry in archive.entries().map_err(archive_error)? {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(AppError::Cancelled);
+        }
+        let mut entry = entry.map_err(archive_error)?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = entry.path().map_err(archive_error)?.into_owned();
+        if !is_metrics_file(&entry_path) {
+            skipped += 1;
+            continue;
+        }
+        let entry_name = format!("{}:{}", path.display(), entry_path.display());
+        let mut bytes = Vec::new();
+        if let Err(e) = entry.read_to_end(&mut bytes) {
+            reject(strict, format!("Failed to read {}: {}", entry_name, e))?;
+            continue;
+        }
+        data.extend(decode_metrics(&entry_name, bytes, strict)?);
+    }
+    if skipped > 0 {
+        logger::warn(format!(
+            "Skipped {} non-JSON entries in {}",
+            skipped,
+            path.display()
+        ));
+    }
+    Ok(merge_layouts(data))
+}
+
+const UNIT_KIND: &str = "unit";
+
+fn contains_units(spaces: &[Space]) -> bool {
+    spaces
+        .iter()
+        .any(|space| space.kind == UNIT_KIND || contains_units(&space.spaces))
+}
+
+/// Moves the source-file units nested anywhere under `spaces` into `units`.
+fn collect_units(spaces: Vec<Space>, units: &mut Vec<JsonData>) {
+    for space in spaces {
+        if space.kind == UNIT_KIND {
+            units.push(JsonData {
+                name: space.name,
+                start_line: space.start_line,
+                end_line: space.end_line,
+                kind: space.kind,
+                spaces: space.spaces,
+                metrics: space.metrics,
+            });
+        } else {
+            collect_units(space.spaces, units);
+        }
+    }
+}
+
+/// Brings per-file and whole-project metrics to one entry per source file. A whole-project
+/// JSON, whose spaces nest the units of its files, is split into those units and its own
+/// aggregate metrics are dropped; a unit of a file that is also reported on its own is dropped
+/// too. Several records for one file, as an NDJSON batch can hold, are all kept.
+pub fn merge_layouts(data: Vec<JsonData>) -> Vec<JsonData> {
+    let mut files = Vec::with_capacity(data.len());
+    let mut units = Vec::new();
+    for entry in data {
+        if contains_units(&entry.spaces) {
+            collect_units(entry.spaces, &mut units);
+        } else {
+            files.push(entry);
+        }
+    }
+    let reported: HashSet<String> = files.iter().map(|file| source_key(&file.name)).collect();
+    let before = units.len();
+    units.retain(|unit| !reported.contains(&source_key(&unit.name)));
+    let duplicates = before - units.len();
+    if duplicates > 0 {
+        logger::warn(format!(
+            "Skipped {} files of whole-project metrics also reported on their own",
+            duplicates
+        ));
+    }
+    files.extend(units);
+    files
+}
+
+fn source_key(name: &str) -> String {
+    name.trim_start_matches("./").to_owned()
+}
+
+type SpaceIdentity = (String, u32, u32, String);
+
+/// Drops every space that repeats an earlier one of the same file with the same name, line
+/// range and kind, such as a function reported by two overlapping analysis runs, and returns
+/// how many were dropped.
+pub fn dedup_spaces(data: &mut [JsonData]) -> usize {
+    data.iter_mut()
+        .map(|file| dedup_nested_spaces(&mut file.spaces, &mut HashSet::new()))
+        .sum()
+}
+
+fn dedup_nested_spaces(spaces: &mut Vec<Space>, seen: &mut HashSet<SpaceIdentity>) -> usize {
+    let before = spaces.len();
+    spaces.retain(|space| {
+        seen.insert((
+            space.name.clone(),
+            space.start_line,
+            space.end_line,
+            space.kind.clone(),
+        ))
+    });
+    let nested: usize = spaces
+        .iter_mut()
+        .map(|space| dedup_nested_spaces(&mut space.spaces, seen))
+        .sum();
+    before - spaces.len() + nested
+}
+
+fn collapse_duplicate_spaces(data: &mut [JsonData], options: &SummaryOptions) {
+    if !options.dedup_spaces {
+        return;
+    }
+    let duplicates = dedup_spaces(data);
+    if duplicates > 0 {
+        logger::warn(format!("Collapsed {} duplicate spaces", duplicates));
+    }
+}
+
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
+pub fn language_of(data: &JsonData) -> &'static str {
+    let extension = Path::new(&data.name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("rs") => "Rust",
+        Some("js" | "jsx" | "mjs" | "cjs") => "JavaScript",
+        Some("ts" | "tsx" | "mts" | "cts") => "TypeScript",
+        Some("py") => "Python",
+        Some("java") => "Java",
+        Some("kt" | "kts") => "Kotlin",
+        Some("c" | "h") => "C",
+        Some("cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx") => "C++",
+        _ => UNKNOWN_LANGUAGE,
+    }
+}
+
+pub const OTHER_EXTENSION: &str = "other";
+
+/// The lowercase extension of the analyzed source file, or `other` for files whose extension
+/// isn't a recognized source language.
+pub fn extension_of(data: &JsonData) -> String {
+    match Path::new(&data.name).extension() {
+        Some(ext) if language_of(data) != UNKNOWN_LANGUAGE => {
+            format!(".{}", ext.to_string_lossy().to_lowercase())
+        }
+        _ => OTHER_EXTENSION.to_owned(),
+    }
+}
+
+pub struct Section {
+    pub title: String,
+    /// Key of the headline value, as used by `--fail-if` and thresholds.
+    pub key: Option<&'static str>,
+    pub details: Option<Vec<(String, String)>>,
+    pub value: Option<f64>,
+    /// Levels of the individual detail rows, for sections judged row by row rather than by
+    /// thresholds on the headline.
+    pub levels: Option<Vec<Level>>,
+}
+
+impl Section {
+    fn new<T: Detailed>(
+        title: impl Into<String>,
+        key: Option<&'static str>,
+        metric: &Option<T>,
+        fmt: &NumberFormat,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            key,
+            details: metric
+                .as_ref()
+                .map(|m| m.details(key.and_then(|key| key.split('.').next()), fmt)),
+            value: metric.as_ref().and_then(Detailed::headline),
+            levels: None,
+        }
+    }
+
+    pub fn level(&self, thresholds: &Thresholds) -> Level {
+        if let Some(levels) = &self.levels {
+            return levels.iter().copied().max().unwrap_or(Level::Ok);
+        }
+        match (self.key, self.value) {
+            (Some(key), Some(value)) => thresholds.level(key, value),
+            _ => Level::Ok,
+        }
+    }
+
+    pub fn headline(&self) -> Option<&(String, String)> {
+        self.details.as_ref().and_then(|details| details.first())
+    }
+}
+
+pub fn sections_to_markdown(title: &str, sections: &[Section]) -> String {
+    let mut markdown = format!("## {}\n\n| Metric | Value |\n| --- | --- |\n", title);
+    for section in sections {
+        match &section.details {
+            Some(details) => {
+                markdown.push_str(&format!("| **{}** | |\n", section.title));
+                for (key, value) in details {
+                    markdown.push_str(&format!("| {} | {} |\n", key, value));
+                }
+            }
+            None => markdown.push_str(&format!("| **{}** | N/A |\n", section.title)),
+        }
+    }
+    markdown
 }
 
-pub fn create_summary_table(summary: MetricsSummary) -> Table<'static> {
+pub fn create_summary_table(
+    sections: &[Section],
+    expanded: &[bool],
+    selected: Option<usize>,
+    offset: usize,
+    thresholds: &Thresholds,
+    history: &[HistoryEntry],
+) -> (Table<'static>, Option<usize>) {
+    let header_style = Style::default()
+        .fg(Color::LightBlue)
+        .add_modifier(Modifier::BOLD);
+    let key_style = Style::default().fg(Color::Yellow);
+    let value_style = Style::default().fg(Color::Green);
+
     let mut rows = Vec::new();
+    let mut selected_row = None;
+
+    for (index, section) in sections.iter().enumerate() {
+        if selected == Some(index) {
+            selected_row = Some(rows.len());
+        }
+        let is_expanded = expanded.get(index).copied().unwrap_or(false);
+        let marker = match (&section.details, is_expanded) {
+            (None, _) => " ",
+            (Some(_), true) => "▾",
+            (Some(_), false) => "▸",
+        };
+        let title = format!("{} {}", marker, section.title);
+        let trend = section
+            .key
+            .and_then(|key| sparkline(history, key))
+            .unwrap_or_default();
+        let level = section.level(thresholds);
+        let value_style = level
+            .color()
+            .map_or(value_style, |color| value_style.fg(color));
+
+        match (&section.details, section.headline()) {
+            (Some(details), _) if is_expanded => {
+                let title_style = level
+                    .color()
+                    .map_or(header_style, |color| header_style.fg(color));
+                rows.push(Row::new(vec![
+                    Cell::from(title).style(title_style.add_modifier(Modifier::UNDERLINED)),
+                    Cell::from(""),
+                    Cell::from(trend),
+                ]));
+                for (index, (key, value)) in details.iter().enumerate() {
+                    let row_style = match section.levels.as_ref().and_then(|l| l.get(index)) {
+                        Some(level) => level
+                            .color()
+                            .map_or(value_style, |color| value_style.fg(color)),
+                        None => value_style,
+                    };
+                    rows.push(Row::new(vec![
+                        Cell::from(format!("  {}", key)).style(key_style),
+                        Cell::from(scroll_text(value, offset)).style(row_style),
+                    ]));
+                }
+            }
+            (Some(_), Some((key, value))) => rows.push(Row::new(vec![
+                Cell::from(title).style(header_style),
+                Cell::from(scroll_text(&format!("{}: {}", key, value), offset)).style(value_style),
+                Cell::from(trend),
+            ])),
+            _ => rows.push(Row::new(vec![
+                Cell::from(title).style(header_style),
+                Cell::from("N/A").style(value_style),
+            ])),
+        }
+    }
+
+    // The trend column only takes room once there are snapshots to draw.
+    let (widths, header) = if history.len() < 2 {
+        (
+            vec![Constraint::Percentage(30), Constraint::Percentage(70)],
+            vec!["Metric", "Value"],
+        )
+    } else {
+        (
+            vec![
+                Constraint::Percentage(30),
+                Constraint::Fill(1),
+                Constraint::Length(SPARKLINE_RUNS as u16),
+            ],
+            vec!["Metric", "Value", "Trend"],
+        )
+    };
+    let table = Table::new(rows, widths)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .column_spacing(3)
+        .style(Style::default().fg(Color::White))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    (table, selected_row)
+}
+
+pub fn create_compact_table(
+    sections: &[Section],
+    fmt: &NumberFormat,
+    offset: usize,
+    thresholds: &Thresholds,
+) -> Table<'static> {
+    let header_style = Style::default()
+        .fg(Color::LightBlue)
+        .add_modifier(Modifier::BOLD);
 
-    add_details!(rows, "NArgs", summary.nargs);
-    add_details!(rows, "NExits", summary.nexits);
-    add_details!(rows, "Cognitive Complexity", summary.cognitive);
-    add_details!(rows, "Cyclomatic Complexity", summary.cyclomatic);
-    add_details!(rows, "Halstead Metrics", summary.halstead);
-    add_details!(rows, "Lines of Code", summary.loc);
-    add_details!(rows, "Number of Methods", summary.nom);
-    add_details!(rows, "Maintainability Index", summary.mi);
-    add_details!(rows, "ABC Complexity", summary.abc);
+    let rows: Vec<Row> = sections
+        .iter()
+        .map(|section| {
+            let color = section.level(thresholds).color().unwrap_or(Color::Green);
+            Row::new(vec![
+                Cell::from(section.title.clone()).style(header_style),
+                Cell::from(scroll_text(&fmt.metric(section.key, section.value), offset))
+                    .style(Style::default().fg(color)),
+            ])
+        })
+        .collect();
 
     Table::new(
         rows,
-        [Constraint::Percentage(30), Constraint::Percentage(70)],
-    )
-    .header(
-        Row::new(vec!["Metric", "Summary"]).style(Style::default().add_modifier(Modifier::BOLD)),
+        [Constraint::Percentage(50), Constraint::Percentage(50)],
     )
+    .header(Row::new(vec!["Metric", "Value"]).style(Style::default().add_modifier(Modifier::BOLD)))
     .column_spacing(3)
-    .block(
-        Block::default()
-            .title("Metrics Summary")
-            .borders(Borders::ALL),
+    .style(Style::default().fg(Color::White))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+/// Shows each headline metric of a single file next to the per-file average of `baseline`,
+/// returning the table and its rows as the plain-text lines used for copying.
+pub fn create_comparison_table(
+    file: &MetricsSummary,
+    baseline: &MetricsSummary,
+    fmt: &NumberFormat,
+) -> (Table<'static>, Vec<String>) {
+    let header_style = Style::default()
+        .fg(Color::LightBlue)
+        .add_modifier(Modifier::BOLD);
+    let files = baseline.files().max(1) as f64;
+
+    let (rows, lines): (Vec<Row>, Vec<String>) = file
+        .sections(fmt)
+        .into_iter()
+        .zip(baseline.sections(fmt))
+        .filter_map(|(section, base)| section.key.map(|key| (key, section, base)))
+        .map(|(key, section, base)| {
+            // The Maintainability Index is already a mean across files; the rest are totals.
+            let average = if key.starts_with("mi.") {
+                base.value
+            } else {
+                base.value.map(|value| value / files)
+            };
+            let (marker, color) = match (section.value, average) {
+                (Some(value), Some(average)) if value != average => {
+                    let worse = (value > average) == higher_is_worse(key);
+                    let color = if worse { Color::Red } else { Color::Green };
+                    (if value > average { "▲" } else { "▼" }, color)
+                }
+                (Some(_), Some(_)) => ("=", Color::White),
+                _ => ("", Color::White),
+            };
+            let line = format!(
+                "{}: {} (dir avg {})",
+                section.title,
+                fmt.metric(Some(key), section.value),
+                fmt.metric(Some(key), average)
+            );
+            let row = Row::new(vec![
+                Cell::from(section.title).style(header_style),
+                Cell::from(fmt.metric(Some(key), section.value)),
+                Cell::from(fmt.metric(Some(key), average)),
+                Cell::from(marker).style(Style::default().fg(color)),
+            ]);
+            (row, line)
+        })
+        .unzip();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(40),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["Metric", "File", "Dir avg", ""])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
     )
+    .column_spacing(2)
     .style(Style::default().fg(Color::White))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    (table, lines)
+}
+
+const NDJSON_EXTENSION: &str = "ndjson";
+const GZIP_EXTENSION: &str = "gz";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+const NESTING_KEY: &str = "nesting.max";
+
+/// Keys of the summary rows that can be broken down into per-file contributions.
+const CONTRIBUTION_KEYS: [&str; 12] = [
+    "nargs.total",
+    "nexits.sum",
+    "cognitive.sum",
+    "cyclomatic.sum",
+    "halstead.volume",
+    "loc.sloc",
+    "nom.total",
+    "mi.mi_original",
+    "mi.mi_sei",
+    "mi.mi_visual_studio",
+    "abc.magnitude",
+    NESTING_KEY,
+];
+
+/// The headline values of a single file, kept after summarizing so that a summary row can be
+/// broken down into the files that make it up.
+#[derive(Debug, Clone)]
+pub struct FileValues {
+    pub name: String,
+    values: [Option<f64>; CONTRIBUTION_KEYS.len()],
+}
+
+impl FileValues {
+    /// Summarizes the file on its own, so that its values are the headlines its summary rows
+    /// would show.
+    pub fn new(data: &JsonData, options: &SummaryOptions) -> Self {
+        let summary = MetricsSummary::summarize(std::slice::from_ref(data), options);
+        Self {
+            name: data.name.clone(),
+            values: CONTRIBUTION_KEYS.map(|key| summary.headline(key)),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<f64> {
+        let index = CONTRIBUTION_KEYS.iter().position(|k| *k == key)?;
+        self.values[index]
+    }
 }
 
-fn read_json_file(file_path: &str) -> Option<JsonData> {
-    fs::read_to_string(file_path)
-        .ok()
-        .and_then(|content| serde_json::from_str::<JsonData>(&content).ok())
-        .or_else(|| {
-            eprintln!("Failed to read or parse {}", file_path);
-            None
+/// Lists the files contributing to the summary row `key`, largest first, with each file's share
+/// of the total. The Maintainability Index is averaged rather than summed, so it is listed
+/// lowest (worst) first and without shares, and the nesting depth is a maximum, so it has no
+/// shares either.
+pub fn create_contributions_table(
+    files: &[FileValues],
+    key: &str,
+    fmt: &NumberFormat,
+) -> (Table<'static>, Vec<String>) {
+    let summed = !key.starts_with("mi.");
+    let shared = summed && key != NESTING_KEY;
+    let mut values: Vec<(&str, f64)> = files
+        .iter()
+        .filter_map(|file| file.get(key).map(|value| (file.name.as_str(), value)))
+        .collect();
+    values.sort_by(|a, b| {
+        let order = b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal);
+        if summed {
+            order
+        } else {
+            order.reverse()
+        }
+    });
+    let total: f64 = values.iter().map(|(_, value)| value).sum();
+    let (rows, lines): (Vec<Row>, Vec<String>) = values
+        .iter()
+        .map(|(name, value)| {
+            let share = if shared && total != 0.0 {
+                format!("{:.1}%", value / total * 100.0)
+            } else {
+                String::new()
+            };
+            let line = if share.is_empty() {
+                format!("{}: {}", name, fmt.metric(Some(key), Some(*value)))
+            } else {
+                format!(
+                    "{}: {} ({})",
+                    name,
+                    fmt.metric(Some(key), Some(*value)),
+                    share
+                )
+            };
+            let row = Row::new(vec![
+                Cell::from(name.to_string()),
+                Cell::from(fmt.metric(Some(key), Some(*value)))
+                    .style(Style::default().fg(Color::Green)),
+                Cell::from(share).style(Style::default().fg(Color::Gray)),
+            ]);
+            (row, line)
         })
+        .unzip();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(14),
+            Constraint::Length(7),
+        ],
+    )
+    .header(
+        Row::new(vec!["File", "Value", "Share"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .column_spacing(1)
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    (table, lines)
+}
+
+/// The extension of a metrics file, looking through a trailing `.gz`.
+fn metrics_extension(path: &Path) -> Option<&std::ffi::OsStr> {
+    match path.extension() {
+        Some(ext) if ext == GZIP_EXTENSION => path.file_stem().map(Path::new)?.extension(),
+        ext => ext,
+    }
 }
 
-fn update_average(old: Option<f64>, count: usize, new: Option<f64>) -> Option<f64> {
-    Some(((old.unwrap_or(0.0) * count as f64) + new.unwrap_or(0.0)) / (count as f64 + 1.0))
+pub fn is_metrics_file(path: &Path) -> bool {
+    metrics_extension(path).is_some_and(|ext| ext == "json" || ext == NDJSON_EXTENSION)
 }
 
-trait Countable {
-    fn add_count(&mut self);
+/// Reads a single metrics file leniently, collapsing duplicate spaces as `options` ask.
+pub fn read_json_file(file_path: &Path, options: &SummaryOptions) -> Vec<JsonData> {
+    let mut data = read_metrics_file(file_path, false).unwrap_or_default();
+    collapse_duplicate_spaces(&mut data, options);
+    data
 }
 
-impl Countable for MetricValuesSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+/// Reads one metrics file. Input that can't be read or parsed is logged and skipped, or with
+/// `strict` returned as an error naming the file.
+pub fn read_metrics_file(file_path: &Path, strict: bool) -> AppResult<Vec<JsonData>> {
+    let name = file_path.display().to_string();
+    match fs::read(file_path) {
+        Ok(bytes) => decode_metrics(&name, bytes, strict),
+        Err(e) => reject(strict, format!("Failed to read {}: {}", name, e)),
     }
 }
-impl Countable for BasicSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+
+fn reject(strict: bool, message: String) -> AppResult<Vec<JsonData>> {
+    if strict {
+        return Err(AppError::AnalysisError(message));
     }
+    logger::warn(message);
+    Ok(Vec::new())
+}
+
+/// Parses raw metrics file contents, decompressing them first when they are gzipped.
+fn decode_metrics(file_path: &str, bytes: Vec<u8>, strict: bool) -> AppResult<Vec<JsonData>> {
+    let gzipped = bytes.starts_with(&GZIP_MAGIC)
+        || Path::new(file_path)
+            .extension()
+            .is_some_and(|ext| ext == GZIP_EXTENSION);
+    let content = if gzipped {
+        let mut content = String::new();
+        if let Err(e) = GzDecoder::new(bytes.as_slice()).read_to_string(&mut content) {
+            return reject(strict, format!("Failed to decompress {}: {}", file_path, e));
+        }
+        content
+    } else {
+        match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(e) => return reject(strict, format!("Failed to read {}: {}", file_path, e)),
+        }
+    };
+    parse_metrics(file_path, &content, strict)
 }
-impl Countable for HalsteadSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+
+fn parse_metrics(file_path: &str, content: &str, strict: bool) -> AppResult<Vec<JsonData>> {
+    let is_ndjson =
+        metrics_extension(Path::new(file_path)).is_some_and(|ext| ext == NDJSON_EXTENSION);
+    if !is_ndjson {
+        // A broken pretty-printed file is not NDJSON: its first line is no JSON value on its
+        // own, so the error that matters is the one for the whole file.
+        let first_line = content.lines().find(|l| !l.trim().is_empty());
+        let single_line = content.lines().filter(|l| !l.trim().is_empty()).count() <= 1;
+        match serde_json::from_str::<JsonData>(content) {
+            Ok(data) => return Ok(vec![data]),
+            Err(e)
+                if single_line
+                    || first_line.is_some_and(|line| {
+                        serde_json::from_str::<serde_json::Value>(line).is_err()
+                    }) =>
+            {
+                return reject(strict, format!("Failed to parse {}: {}", file_path, e));
+            }
+            Err(_) => {}
+        }
+    }
+    read_ndjson(file_path, content, strict)
+}
+
+fn read_ndjson(file_path: &str, content: &str, strict: bool) -> AppResult<Vec<JsonData>> {
+    let mut skipped = 0;
+    let mut data = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<JsonData>(line) {
+            Ok(parsed) => data.push(parsed),
+            Err(e) if strict => {
+                return Err(AppError::AnalysisError(format!(
+                    "Failed to parse {} line {}: {}",
+                    file_path,
+                    number + 1,
+                    e
+                )));
+            }
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        logger::warn(format!(
+            "Skipped {} unparseable lines in {}",
+            skipped, file_path
+        ));
+    }
+    Ok(data)
+}
+
+/// Metric families that can be summarized, as named by `--metrics` and `RCA_TUI_METRICS`.
+pub const METRIC_NAMES: [&str; 9] = [
+    "nargs",
+    "nexits",
+    "cognitive",
+    "cyclomatic",
+    "halstead",
+    "loc",
+    "nom",
+    "mi",
+    "abc",
+];
+
+/// A selection of metric families; every family is selected by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricSet(u16);
+
+impl Default for MetricSet {
+    fn default() -> Self {
+        Self((1 << METRIC_NAMES.len()) - 1)
     }
 }
-impl Countable for LocSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+
+impl MetricSet {
+    /// Whether the family of `key` (the part before the first `.`) is selected.
+    pub fn contains(self, key: &str) -> bool {
+        let family = key.split('.').next().unwrap_or(key);
+        METRIC_NAMES
+            .iter()
+            .position(|name| *name == family)
+            .is_some_and(|index| self.0 & (1 << index) != 0)
     }
 }
-impl Countable for NomSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+
+impl std::str::FromStr for MetricSet {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = Self(0);
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let index = METRIC_NAMES
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    AppError::InvalidArgument(format!(
+                        "Unknown metric '{}' (expected one of: {})",
+                        name,
+                        METRIC_NAMES.join(", ")
+                    ))
+                })?;
+            set.0 |= 1 << index;
+        }
+        if set.0 == 0 {
+            return Err(AppError::InvalidArgument("No metrics selected".into()));
+        }
+        Ok(set)
     }
 }
-impl Countable for MiSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+
+/// Which Maintainability Index variant is the headline of its summary row, and so the one
+/// judged by thresholds, shown in compact mode and scored in the health score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MiVariant {
+    #[default]
+    Original,
+    Sei,
+    VisualStudio,
+}
+
+impl MiVariant {
+    pub const ALL: [MiVariant; 3] = [MiVariant::Original, MiVariant::Sei, MiVariant::VisualStudio];
+
+    /// The summary key of this variant, such as `mi.mi_sei`.
+    pub fn key(self) -> &'static str {
+        match self {
+            MiVariant::Original => "mi.mi_original",
+            MiVariant::Sei => "mi.mi_sei",
+            MiVariant::VisualStudio => "mi.mi_visual_studio",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MiVariant::Original => "MI Original",
+            MiVariant::Sei => "MI SEI",
+            MiVariant::VisualStudio => "MI VS",
+        }
+    }
+
+    /// Moves settings keyed by any MI variant, such as health weights, budgets or thresholds,
+    /// onto this one, as the headline is the MI that is judged. The setting keyed by this
+    /// variant wins, else the first other MI setting stands in for it; the rest are dropped so
+    /// the MI counts once. Other settings are kept in place.
+    pub fn resolve<'a, T: Copy>(
+        self,
+        settings: impl IntoIterator<Item = (&'a str, T)>,
+    ) -> Vec<(&'a str, T)> {
+        let settings: Vec<_> = settings.into_iter().collect();
+        let is_mi = |key: &str| key.starts_with("mi.");
+        let mi = settings
+            .iter()
+            .find(|(key, _)| *key == self.key())
+            .or_else(|| settings.iter().find(|(key, _)| is_mi(key)))
+            .map(|&(_, value)| value);
+        let mut resolved = Vec::with_capacity(settings.len());
+        let mut placed = false;
+        for (key, value) in settings {
+            if !is_mi(key) {
+                resolved.push((key, value));
+            } else if let (false, Some(mi)) = (placed, mi) {
+                resolved.push((self.key(), mi));
+                placed = true;
+            }
+        }
+        resolved
     }
 }
-impl Countable for AbcSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+
+impl std::str::FromStr for MiVariant {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "original" => Ok(MiVariant::Original),
+            "sei" => Ok(MiVariant::Sei),
+            "visual_studio" | "vs" => Ok(MiVariant::VisualStudio),
+            _ => Err(AppError::InvalidArgument(format!(
+                "Unknown MI variant '{}', expected original, sei or visual_studio",
+                s
+            ))),
+        }
     }
 }
 
-impl Countable for WmcSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryOptions {
+    pub weighted: bool,
+    /// Skip files matched by `.gitignore` and `.ignore` rules.
+    pub respect_ignore: bool,
+    pub metrics: MetricSet,
+    /// Fail on the first metrics file that can't be parsed instead of skipping it.
+    pub strict: bool,
+    /// Count spaces repeated within a file, with the same name, lines and kind, only once.
+    pub dedup_spaces: bool,
+    /// Walk into symlinked directories and read symlinked files.
+    pub follow_symlinks: bool,
+    /// Extra metrics read from the raw JSON, configured under `custom_metrics`.
+    pub custom: Vec<CustomMetric>,
+    pub mi_variant: MiVariant,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            weighted: false,
+            respect_ignore: true,
+            metrics: MetricSet::default(),
+            strict: false,
+            dedup_spaces: false,
+            follow_symlinks: false,
+            custom: Vec::new(),
+            mi_variant: MiVariant::default(),
+        }
     }
 }
 
-impl Countable for NpmSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+/// What one file counts for in the averages: one file, or its SLOC with `--weighted`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weight {
+    File,
+    Sloc(f64),
+}
+
+impl Weight {
+    /// The weight of a file's metrics. Under `--weighted`, a file without SLOC counts as a
+    /// single line instead of dropping out of the averages.
+    fn of(metrics: &Metrics, weighted: bool) -> Self {
+        if !weighted {
+            return Weight::File;
+        }
+        let sloc = metrics.loc.as_ref().and_then(|loc| loc.sloc);
+        Weight::Sloc(sloc.filter(|sloc| *sloc > 0.0).unwrap_or(1.0))
     }
 }
 
-impl Countable for NpaSummary {
-    fn add_count(&mut self) {
-        self.count += 1;
+/// How many files went into a summary, and their total SLOC under `--weighted`. Counts
+/// saturate at `usize::MAX` instead of wrapping.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy)]
+pub struct Tally {
+    pub count: usize,
+    #[serde(skip)]
+    sloc: f64,
+}
+
+impl Tally {
+    /// The share a new file takes of the mean over everything tallied so far plus it: one
+    /// over the file count, or its part of the SLOC when weighted.
+    fn share(&self, weight: Weight) -> f64 {
+        match weight {
+            Weight::File => 1.0 / (self.count as f64 + 1.0),
+            Weight::Sloc(sloc) => sloc / (self.sloc + sloc),
+        }
+    }
+
+    fn add(&mut self, weight: Weight) {
+        self.count = self.count.saturating_add(1);
+        if let Weight::Sloc(sloc) = weight {
+            self.sloc += sloc;
+        }
     }
 }
 
-fn merge_with<T, M, F>(current: Option<T>, metric: &Option<M>, updater: F) -> Option<T>
+/// Moves the mean `old` towards `new` by the new file's `share`, rather than re-summing
+/// `old * count`, so huge counts or weights neither overflow nor drift away from a repeated
+/// value.
+fn running_mean(old: f64, new: f64, share: f64) -> f64 {
+    old + (new - old) * share
+}
+
+fn update_average(old: Option<f64>, share: f64, new: Option<f64>) -> Option<f64> {
+    Some(running_mean(old.unwrap_or(0.0), new.unwrap_or(0.0), share))
+}
+
+/// Summaries that count the files merged into them in a `tally` field.
+trait Tallied {
+    fn tally(&mut self) -> &mut Tally;
+}
+
+macro_rules! tallied {
+    ($($summary:ty),* $(,)?) => {
+        $(impl Tallied for $summary {
+            fn tally(&mut self) -> &mut Tally {
+                &mut self.tally
+            }
+        })*
+    };
+}
+
+tallied!(
+    MetricValuesSummary,
+    BasicSummary,
+    HalsteadSummary,
+    LocSummary,
+    NomSummary,
+    MiSummary,
+    AbcSummary,
+    WmcSummary,
+    NpmSummary,
+    NpaSummary,
+);
+
+fn merge_with<T, M, F>(
+    current: Option<T>,
+    metric: &Option<M>,
+    weight: Weight,
+    updater: F,
+) -> Option<T>
 where
-    T: Default + Clone + Copy + Countable,
-    F: Fn(&mut T, &M),
+    T: Default + Clone + Copy + Tallied,
+    F: Fn(&mut T, &M, f64),
 {
     metric
         .as_ref()
         .map(|m| {
             let mut summary = current.unwrap_or_default();
-            updater(&mut summary, m);
-            summary.add_count();
+            let share = summary.tally().share(weight);
+            updater(&mut summary, m, share);
+            summary.tally().add(weight);
             summary
         })
         .or(current)
 }
 
+/// The smaller of two optional values, ignoring a missing one.
+fn lowest(current: Option<f64>, new: Option<f64>) -> Option<f64> {
+    match (current, new) {
+        (Some(current), Some(new)) => Some(current.min(new)),
+        (current, new) => current.or(new),
+    }
+}
+
+/// The larger of two optional values, ignoring a missing one.
+fn highest(current: Option<f64>, new: Option<f64>) -> Option<f64> {
+    match (current, new) {
+        (Some(current), Some(new)) => Some(current.max(new)),
+        (current, new) => current.or(new),
+    }
+}
+
+/// True when a reported total matches the sum of its parts, allowing for rounding.
+fn reconciles(total: f64, parts: f64) -> bool {
+    (total - parts).abs() <= 1e-6 * total.abs().max(1.0)
+}
+
 pub trait Merge: Sized + Clone + std::fmt::Debug + 'static {
     type Metric;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self>;
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self>;
 }
 
 trait Detailed {
-    fn details(&self) -> Vec<(String, String)>;
+    /// The rows of the metric, formatted with the precision of their full key. `family` is the
+    /// part of the section key before the first `.`, for summaries shared by several metrics.
+    fn details(&self, family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)>;
+    /// The single number shown for this metric in compact mode.
+    fn headline(&self) -> Option<f64>;
+}
+
+/// Lays out the total and average rows of a block as `fmt.values` asks, followed by the rows
+/// that are neither.
+fn by_value_mode(
+    fmt: &NumberFormat,
+    totals: Vec<(String, String)>,
+    averages: Vec<(String, String)>,
+    rest: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    let shown = match fmt.values {
+        ValueMode::Both => [totals, averages].concat(),
+        ValueMode::Totals => totals,
+        ValueMode::Averages => averages,
+    };
+    [shown, rest].concat()
 }
 
-#[derive(Debug, Serialize, Default)]
+#[derive(Debug, Serialize, Default, Clone)]
 pub struct MetricsSummary {
+    files: usize,
+    kinds: Option<KindSummary>,
+    nesting: Option<NestingSummary>,
     nargs: Option<MetricValuesSummary>,
     nexits: Option<BasicSummary>,
     cognitive: Option<BasicSummary>,
@@ -198,25 +1247,619 @@ pub struct MetricsSummary {
     nom: Option<NomSummary>,
     mi: Option<MiSummary>,
     abc: Option<AbcSummary>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    extensions: BTreeMap<String, ExtensionSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    custom: Vec<CustomSummary>,
+    #[serde(skip)]
+    metrics: MetricSet,
+    #[serde(skip)]
+    mi_variant: MiVariant,
 }
 
 impl MetricsSummary {
-    pub fn summarize(json_data: Vec<JsonData>) -> Self {
-        json_data.iter().flat_map(|d| d.metrics.as_ref()).fold(
-            Default::default(),
-            |mut summary, metrics| {
-                summary.nargs = MetricValuesSummary::merge(summary.nargs, &metrics.nargs);
-                summary.nexits = BasicSummary::merge(summary.nexits, &metrics.nexits);
-                summary.cognitive = BasicSummary::merge(summary.cognitive, &metrics.cognitive);
-                summary.cyclomatic = BasicSummary::merge(summary.cyclomatic, &metrics.cyclomatic);
-                summary.halstead = HalsteadSummary::merge(summary.halstead, &metrics.halstead);
-                summary.loc = LocSummary::merge(summary.loc, &metrics.loc);
-                summary.nom = NomSummary::merge(summary.nom, &metrics.nom);
-                summary.mi = MiSummary::merge(summary.mi, &metrics.mi);
-                summary.abc = AbcSummary::merge(summary.abc, &metrics.abc);
+    pub fn summarize(json_data: &[JsonData], options: &SummaryOptions) -> Self {
+        Self::summarize_iter(json_data.iter(), options)
+    }
+
+    fn summarize_iter<'a>(
+        json_data: impl Iterator<Item = &'a JsonData>,
+        options: &SummaryOptions,
+    ) -> Self {
+        let initial = Self {
+            custom: options.custom.iter().map(CustomSummary::new).collect(),
+            metrics: options.metrics,
+            mi_variant: options.mi_variant,
+            ..Self::default()
+        };
+        let selected = |key: &str| options.metrics.contains(key);
+        // An NDJSON batch can hold several records for one source file.
+        let mut sources = HashSet::new();
+        json_data.fold(initial, |mut summary, data| {
+            if sources.insert(data.name.as_str()) {
+                summary.files += 1;
                 summary
-            },
+                    .extensions
+                    .entry(extension_of(data))
+                    .or_default()
+                    .files += 1;
+            }
+            let kinds = summary.kinds.get_or_insert_with(KindSummary::default);
+            kinds.add(&data.kind);
+            kinds.add_spaces(&data.spaces);
+            summary
+                .nesting
+                .get_or_insert_with(NestingSummary::default)
+                .add_spaces(&data.spaces, 1);
+            let extension = summary.extensions.entry(extension_of(data)).or_default();
+            let Some(metrics) = data.metrics.as_ref() else {
+                return summary;
+            };
+            extension.add(metrics);
+            let weight = Weight::of(metrics, options.weighted);
+            if selected("nargs") {
+                summary.nargs = MetricValuesSummary::merge(summary.nargs, &metrics.nargs, weight);
+            }
+            if selected("nexits") {
+                summary.nexits = BasicSummary::merge(summary.nexits, &metrics.nexits, weight);
+            }
+            if selected("cognitive") {
+                summary.cognitive =
+                    BasicSummary::merge(summary.cognitive, &metrics.cognitive, weight);
+            }
+            if selected("cyclomatic") {
+                summary.cyclomatic =
+                    BasicSummary::merge(summary.cyclomatic, &metrics.cyclomatic, weight);
+            }
+            if selected("halstead") {
+                summary.halstead =
+                    HalsteadSummary::merge(summary.halstead, &metrics.halstead, weight);
+            }
+            if selected("loc") {
+                summary.loc = LocSummary::merge(summary.loc, &metrics.loc, weight);
+            }
+            if selected("nom") {
+                summary.nom = NomSummary::merge(summary.nom, &metrics.nom, weight);
+            }
+            if selected("mi") {
+                summary.mi =
+                    MiSummary::merge(summary.mi, &metrics.mi, weight).map(|mi| MiSummary {
+                        variant: options.mi_variant,
+                        ..mi
+                    });
+            }
+            if selected("abc") {
+                summary.abc = AbcSummary::merge(summary.abc, &metrics.abc, weight);
+            }
+            if !options.custom.is_empty() {
+                let raw = raw_metrics(metrics);
+                for (custom, metric) in summary.custom.iter_mut().zip(&options.custom) {
+                    if let Some(value) = metric.extract(&raw) {
+                        custom.add(value, weight);
+                    }
+                }
+            }
+            summary
+        })
+    }
+
+    pub fn values(&self) -> BTreeMap<String, f64> {
+        serde_json::to_value(self)
+            .map(|value| flatten_values(&value))
+            .unwrap_or_default()
+    }
+
+    /// Each value of this summary next to the same value in `baseline`, as read by
+    /// `read_baseline`, in key order. Values missing from one side are kept with `None` there.
+    pub fn diff(&self, baseline: &BTreeMap<String, f64>) -> Vec<MetricDelta> {
+        let current = self.values();
+        let keys: std::collections::BTreeSet<&String> =
+            current.keys().chain(baseline.keys()).collect();
+        keys.into_iter()
+            .map(|key| MetricDelta::new(key, baseline.get(key).copied(), current.get(key).copied()))
+            .collect()
+    }
+
+    /// The number of distinct source files summarized.
+    pub fn files(&self) -> usize {
+        self.files
+    }
+
+    /// The value of the summary row `key` as its collapsed row shows it, or `None` when the
+    /// row has no value or isn't one of `CONTRIBUTION_KEYS`.
+    fn headline(&self, key: &str) -> Option<f64> {
+        match key {
+            "nargs.total" => self.nargs.as_ref()?.headline(),
+            "nexits.sum" => self.nexits.as_ref()?.headline(),
+            "cognitive.sum" => self.cognitive.as_ref()?.headline(),
+            "cyclomatic.sum" => self.cyclomatic.as_ref()?.headline(),
+            "halstead.volume" => self.halstead.as_ref()?.headline(),
+            "loc.sloc" => self.loc.as_ref()?.headline(),
+            "nom.total" => self.nom.as_ref()?.headline(),
+            "mi.mi_original" | "mi.mi_sei" | "mi.mi_visual_studio" => {
+                (key == self.mi_variant.key()).then_some(self.mi.as_ref()?.headline()?)
+            }
+            "abc.magnitude" => self.abc.as_ref()?.headline(),
+            NESTING_KEY => self.nesting.as_ref()?.headline(),
+            _ => None,
+        }
+    }
+
+    pub fn mi_variant(&self) -> MiVariant {
+        self.mi_variant
+    }
+
+    /// Divides every total by the number of functions (NOM total), so that small and large
+    /// modules compare fairly. Averages, minimums, maximums and the Maintainability Index are
+    /// left as they are, as is the whole summary when it has no functions.
+    pub fn per_function(&self) -> Self {
+        let mut normalized = self.clone();
+        let functions = self.nom.map_or(0.0, |nom| nom.total);
+        if functions <= 0.0 {
+            return normalized;
+        }
+        if let Some(nargs) = normalized.nargs.as_mut() {
+            nargs.normalize(functions);
+        }
+        for basic in [
+            &mut normalized.nexits,
+            &mut normalized.cognitive,
+            &mut normalized.cyclomatic,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            basic.normalize(functions);
+        }
+        if let Some(halstead) = normalized.halstead.as_mut() {
+            halstead.normalize(functions);
+        }
+        if let Some(loc) = normalized.loc.as_mut() {
+            loc.normalize(functions);
+        }
+        if let Some(abc) = normalized.abc.as_mut() {
+            abc.normalize(functions);
+        }
+        for custom in &mut normalized.custom {
+            custom.normalize(functions);
+        }
+        normalized
+    }
+
+    /// Messages describing totals in the input that don't add up across related metrics.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        problems.extend(self.nom.iter().flat_map(NomSummary::validate));
+        problems.extend(self.halstead.iter().flat_map(HalsteadSummary::validate));
+        problems
+    }
+
+    pub fn overview(&self, fmt: &NumberFormat) -> String {
+        let whole = fmt.with_decimals(0);
+        let mut overview = format!(
+            "Files: {}  SLOC: {}  Functions: {}",
+            fmt.count(self.files),
+            whole.optional(self.loc.map(|loc| loc.sloc)),
+            whole.optional(self.nom.map(|nom| nom.total))
+        );
+        let problems = self.validate().len();
+        if problems > 0 {
+            overview.push_str(&format!(
+                "  ⚠ {} totals don't reconcile (see log)",
+                problems
+            ));
+        }
+        overview
+    }
+
+    fn extension_total(&self) -> ExtensionSummary {
+        self.extensions
+            .values()
+            .fold(ExtensionSummary::default(), |mut total, summary| {
+                total.files += summary.files;
+                total.sloc += summary.sloc;
+                total.functions += summary.functions;
+                total.cyclomatic += summary.cyclomatic;
+                total.cognitive += summary.cognitive;
+                total
+            })
+    }
+
+    pub fn sections(&self, fmt: &NumberFormat) -> Vec<Section> {
+        let extension_total = self.extension_total();
+        vec![
+            Section::new("NArgs", Some("nargs.total"), &self.nargs, fmt),
+            Section::new("NExits", Some("nexits.sum"), &self.nexits, fmt),
+            Section::new(
+                "Cognitive Complexity",
+                Some("cognitive.sum"),
+                &self.cognitive,
+                fmt,
+            ),
+            Section::new(
+                "Cyclomatic Complexity",
+                Some("cyclomatic.sum"),
+                &self.cyclomatic,
+                fmt,
+            ),
+            Section::new(
+                "Halstead Metrics",
+                Some("halstead.volume"),
+                &self.halstead,
+                fmt,
+            ),
+            Section::new("Lines of Code", Some("loc.sloc"), &self.loc, fmt),
+            Section::new("Number of Methods", Some("nom.total"), &self.nom, fmt),
+            Section::new(
+                "Maintainability Index",
+                Some(self.mi_variant.key()),
+                &self.mi,
+                fmt,
+            ),
+            Section::new("ABC Complexity", Some("abc.magnitude"), &self.abc, fmt),
+            Section::new("Space Kinds", None, &self.kinds, fmt),
+        ]
+        .into_iter()
+        .filter(|section| section.key.is_none_or(|key| self.metrics.contains(key)))
+        .chain([Section::new(
+            "Nesting Depth",
+            Some(NESTING_KEY),
+            &self.nesting,
+            fmt,
+        )])
+        .chain(
+            self.custom
+                .iter()
+                .map(|custom| Section::new(custom.label.clone(), None, &Some(custom.clone()), fmt)),
+        )
+        .chain(
+            self.extensions
+                .iter()
+                .filter(|_| self.extensions.len() > 1)
+                .map(|(extension, summary)| {
+                    let breakdown = ExtensionBreakdown {
+                        summary: *summary,
+                        total: extension_total,
+                    };
+                    Section::new(
+                        format!("Extension {}", extension),
+                        None,
+                        &Some(breakdown),
+                        fmt,
+                    )
+                }),
         )
+        .collect()
+    }
+}
+
+/// Name of the group of files sitting directly in the shared directory rather than below it.
+pub const TOP_LEVEL_DIRECTORY: &str = ".";
+
+/// Partitions files by the first directory of their source path below the deepest directory
+/// they all share, so files under `crates/*/src` are grouped per crate. Files directly in the
+/// shared directory are grouped under `.`. Source paths are relative to wherever
+/// rust-code-analysis ran, not to the analyzed metrics directory, so the shared directory
+/// stands in for the project root.
+pub fn group_by_directory(json_data: &[JsonData]) -> BTreeMap<String, Vec<&JsonData>> {
+    let components: Vec<Vec<Component>> = json_data
+        .iter()
+        .map(|data| {
+            Path::new(&data.name)
+                .components()
+                .filter(|c| *c != Component::CurDir)
+                .collect()
+        })
+        .collect();
+    let shared = components
+        .iter()
+        .map(|path| &path[..path.len().saturating_sub(1)])
+        .reduce(|shared, dir| {
+            let len = shared.iter().zip(dir).take_while(|(a, b)| a == b).count();
+            &shared[..len]
+        })
+        .map_or(0, <[Component]>::len);
+    let mut directories: BTreeMap<String, Vec<&JsonData>> = BTreeMap::new();
+    for (data, path) in json_data.iter().zip(&components) {
+        let directory = match path.get(shared) {
+            Some(dir) if path.len() > shared + 1 => {
+                format!("{}/", dir.as_os_str().to_string_lossy())
+            }
+            _ => TOP_LEVEL_DIRECTORY.to_owned(),
+        };
+        directories.entry(directory).or_default().push(data);
+    }
+    directories
+}
+
+/// A named share of the analyzed files, such as a language or a subdirectory, with its
+/// summary and the per-file values behind it.
+#[derive(Debug, Clone)]
+pub struct SummaryGroup {
+    pub name: String,
+    pub summary: MetricsSummary,
+    pub files: Vec<FileValues>,
+}
+
+impl SummaryGroup {
+    pub fn new(name: impl Into<String>, data: &[&JsonData], options: &SummaryOptions) -> Self {
+        Self {
+            name: name.into(),
+            summary: MetricsSummary::summarize_iter(data.iter().copied(), options),
+            files: data
+                .iter()
+                .map(|file| FileValues::new(file, options))
+                .collect(),
+        }
+    }
+
+    fn partition(groups: BTreeMap<String, Vec<&JsonData>>, options: &SummaryOptions) -> Vec<Self> {
+        groups
+            .into_iter()
+            .map(|(name, data)| Self::new(name, &data, options))
+            .collect()
+    }
+
+    /// One group per language, as told by `language_of`.
+    pub fn by_language(json_data: &[JsonData], options: &SummaryOptions) -> Vec<Self> {
+        let mut languages: BTreeMap<String, Vec<&JsonData>> = BTreeMap::new();
+        for data in json_data {
+            languages
+                .entry(language_of(data).to_owned())
+                .or_default()
+                .push(data);
+        }
+        Self::partition(languages, options)
+    }
+
+    /// One group per subdirectory, as grouped by `group_by_directory`.
+    pub fn by_directory(json_data: &[JsonData], options: &SummaryOptions) -> Vec<Self> {
+        Self::partition(group_by_directory(json_data), options)
+    }
+}
+
+/// Lists subdirectory summaries with a few headline values each, marking the one whose
+/// summary is `shown` as open.
+pub fn create_directories_table(
+    directories: &[SummaryGroup],
+    shown: Option<usize>,
+    fmt: &NumberFormat,
+) -> (Table<'static>, Vec<String>) {
+    let whole = fmt.with_decimals(0);
+    let (rows, lines): (Vec<Row>, Vec<String>) = directories
+        .iter()
+        .enumerate()
+        .map(|(index, SummaryGroup { name, summary, .. })| {
+            let values = summary.values();
+            let value = |key: &str| values.get(key).copied();
+            let marker = if shown == Some(index) { '▾' } else { '▸' };
+            let line = format!(
+                "{}: {} files, SLOC {}, cyclomatic {}, MI {}",
+                name,
+                summary.files(),
+                whole.optional(value("loc.sloc")),
+                whole.optional(value("cyclomatic.sum")),
+                fmt.metric(
+                    Some(summary.mi_variant.key()),
+                    value(summary.mi_variant.key())
+                )
+            );
+            let row = Row::new(vec![
+                Cell::from(format!("{} {}", marker, name))
+                    .style(Style::default().fg(Color::Yellow)),
+                Cell::from(fmt.count(summary.files())),
+                Cell::from(whole.optional(value("loc.sloc"))),
+                Cell::from(whole.optional(value("cyclomatic.sum"))),
+                Cell::from(fmt.metric(
+                    Some(summary.mi_variant.key()),
+                    value(summary.mi_variant.key()),
+                )),
+            ]);
+            (row, line)
+        })
+        .unzip();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec!["Directory", "Files", "SLOC", "Cyclomatic", "MI"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .column_spacing(1)
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    (table, lines)
+}
+
+/// Flattens a serialized summary into `metric.field` keys, skipping anything that isn't a
+/// number, such as per-directory summaries or hotspot lists.
+fn flatten_values(summary: &serde_json::Value) -> BTreeMap<String, f64> {
+    let mut values = BTreeMap::new();
+    let serde_json::Value::Object(metrics) = summary else {
+        return values;
+    };
+    for (metric, value) in metrics {
+        match value {
+            serde_json::Value::Object(fields) => {
+                for (field, value) in fields {
+                    if let Some(number) = value.as_f64() {
+                        values.insert(format!("{}.{}", metric, field), number);
+                    }
+                }
+            }
+            value => {
+                if let Some(number) = value.as_f64() {
+                    values.insert(metric.clone(), number);
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Reads the values of a summary saved from `--json`, to diff against.
+pub fn read_baseline(path: &Path) -> AppResult<BTreeMap<String, f64>> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        AppError::AnalysisError(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let summary: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+        AppError::AnalysisError(format!("Failed to parse {}: {}", path.display(), e))
+    })?;
+    Ok(flatten_values(&summary))
+}
+
+/// How one summary value moved between a baseline and the current analysis.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: Option<f64>,
+    pub current: Option<f64>,
+    pub delta: Option<f64>,
+}
+
+impl MetricDelta {
+    fn new(metric: &str, baseline: Option<f64>, current: Option<f64>) -> Self {
+        Self {
+            metric: metric.to_owned(),
+            baseline,
+            current,
+            delta: baseline
+                .zip(current)
+                .map(|(baseline, current)| current - baseline),
+        }
+    }
+}
+
+/// Renders deltas as CSV with a `metric,baseline,current,delta` header; missing values are
+/// written as `N/A`.
+pub fn diff_to_csv(deltas: &[MetricDelta]) -> String {
+    let cell = |value: Option<f64>| value.map_or_else(|| "N/A".to_owned(), |v| v.to_string());
+    let mut csv = String::from("metric,baseline,current,delta\n");
+    for delta in deltas {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&delta.metric),
+            cell(delta.baseline),
+            cell(delta.current),
+            cell(delta.delta)
+        ));
+    }
+    csv
+}
+
+#[derive(Serialize)]
+struct FileSummaryLine<'a> {
+    file: &'a str,
+    summary: MetricsSummary,
+}
+
+/// Writes one `{"file": ..., "summary": {...}}` object per line, summarizing and writing each
+/// file in turn so the output is never held in memory as a whole.
+pub fn write_file_summaries<W: Write>(
+    mut writer: W,
+    json_data: &[JsonData],
+    options: &SummaryOptions,
+) -> AppResult<()> {
+    let write_error =
+        |e: std::io::Error| AppError::AnalysisError(format!("Failed to write: {}", e));
+    for data in json_data {
+        let line = FileSummaryLine {
+            file: &data.name,
+            summary: MetricsSummary::summarize(std::slice::from_ref(data), options),
+        };
+        serde_json::to_writer(&mut writer, &line).map_err(|e| {
+            AppError::AnalysisError(format!("Failed to serialize {}: {}", data.name, e))
+        })?;
+        writer.write_all(b"\n").map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[derive(Default, Debug, Serialize, Clone)]
+#[serde(transparent)]
+pub struct KindSummary {
+    counts: BTreeMap<String, usize>,
+}
+
+impl KindSummary {
+    fn add(&mut self, kind: &str) {
+        *self.counts.entry(kind.to_owned()).or_default() += 1;
+    }
+
+    fn add_spaces(&mut self, spaces: &[Space]) {
+        for space in spaces {
+            self.add(&space.kind);
+            self.add_spaces(&space.spaces);
+        }
+    }
+}
+
+/// How deeply spaces (functions, closures, impls…) are nested, derived from the space tree: a
+/// space directly inside a file is at depth 1, a closure inside it at depth 2.
+#[derive(Default, Debug, Serialize, Clone, Copy)]
+pub struct NestingSummary {
+    max: f64,
+    average: f64,
+    count: usize,
+    #[serde(skip)]
+    total: f64,
+}
+
+impl NestingSummary {
+    fn add_spaces(&mut self, spaces: &[Space], depth: usize) {
+        for space in spaces {
+            let depth_value = depth as f64;
+            self.count = self.count.saturating_add(1);
+            self.total += depth_value;
+            self.max = self.max.max(depth_value);
+            self.average = self.total / self.count as f64;
+            self.add_spaces(&space.spaces, depth + 1);
+        }
+    }
+}
+
+/// A per-extension mini-summary of the headline totals.
+#[derive(Default, Debug, Serialize, Clone, Copy)]
+pub struct ExtensionSummary {
+    files: usize,
+    sloc: f64,
+    functions: f64,
+    cyclomatic: f64,
+    cognitive: f64,
+}
+
+impl ExtensionSummary {
+    fn add(&mut self, metrics: &Metrics) {
+        self.sloc += metrics.loc.as_ref().and_then(|m| m.sloc).unwrap_or(0.0);
+        self.functions += metrics
+            .nom
+            .as_ref()
+            .and_then(|m| m.functions)
+            .unwrap_or(0.0);
+        self.cyclomatic += metrics
+            .cyclomatic
+            .as_ref()
+            .and_then(|m| m.sum)
+            .unwrap_or(0.0);
+        self.cognitive += metrics
+            .cognitive
+            .as_ref()
+            .and_then(|m| m.sum)
+            .unwrap_or(0.0);
     }
 }
 
@@ -232,41 +1875,46 @@ pub struct MetricValuesSummary {
     pub functions_max: Option<f64>,
     pub closures_min: Option<f64>,
     pub closures_max: Option<f64>,
-    pub count: usize,
+    /// Smallest and largest total of a single file.
+    pub file_min: Option<f64>,
+    pub file_max: Option<f64>,
+    #[serde(flatten)]
+    pub tally: Tally,
+}
+
+impl MetricValuesSummary {
+    fn normalize(&mut self, by: f64) {
+        for total in [
+            &mut self.total_functions,
+            &mut self.total_closures,
+            &mut self.total,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *total /= by;
+        }
+    }
 }
 
 impl Merge for MetricValuesSummary {
     type Metric = MetricValues;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, w| {
             s.total_functions =
                 Some(s.total_functions.unwrap_or(0.0) + m.total_functions.unwrap_or(0.0));
             s.total_closures =
                 Some(s.total_closures.unwrap_or(0.0) + m.total_closures.unwrap_or(0.0));
             s.total = Some(s.total.unwrap_or(0.0) + m.total.unwrap_or(0.0));
-            s.average_functions = update_average(s.average_functions, s.count, m.average_functions);
-            s.average_closures = update_average(s.average_closures, s.count, m.average_closures);
-            s.average = update_average(s.average, s.count, m.average);
-            s.functions_min = Some(
-                s.functions_min
-                    .unwrap_or(f64::MAX)
-                    .min(m.functions_min.unwrap_or(f64::MAX)),
-            );
-            s.functions_max = Some(
-                s.functions_max
-                    .unwrap_or(f64::MIN)
-                    .max(m.functions_max.unwrap_or(f64::MIN)),
-            );
-            s.closures_min = Some(
-                s.closures_min
-                    .unwrap_or(f64::MAX)
-                    .min(m.closures_min.unwrap_or(f64::MAX)),
-            );
-            s.closures_max = Some(
-                s.closures_max
-                    .unwrap_or(f64::MIN)
-                    .max(m.closures_max.unwrap_or(f64::MIN)),
-            );
+            s.average_functions = update_average(s.average_functions, w, m.average_functions);
+            s.average_closures = update_average(s.average_closures, w, m.average_closures);
+            s.average = update_average(s.average, w, m.average);
+            s.functions_min = lowest(s.functions_min, m.functions_min);
+            s.functions_max = highest(s.functions_max, m.functions_max);
+            s.closures_min = lowest(s.closures_min, m.closures_min);
+            s.closures_max = highest(s.closures_max, m.closures_max);
+            s.file_min = lowest(s.file_min, m.total);
+            s.file_max = highest(s.file_max, m.total);
         })
     }
 }
@@ -275,29 +1923,47 @@ impl Merge for MetricValuesSummary {
 pub struct BasicSummary {
     sum: f64,
     average: f64,
-    min: f64,
-    max: f64,
-    count: usize,
+    /// Smallest and largest value of a single function or other space, among those reporting
+    /// one.
+    min: Option<f64>,
+    max: Option<f64>,
+    /// Smallest and largest sum of a single file, among files reporting one.
+    file_min: Option<f64>,
+    file_max: Option<f64>,
+    #[serde(flatten)]
+    tally: Tally,
 }
 
 impl std::fmt::Display for BasicSummary {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "Sum: {:.2}\nAverage: {:.2}\nMin: {:.2}\nMax: {:.2}\nCount: {}",
-            self.sum, self.average, self.min, self.max, self.count
+            "Sum: {:.2}\nAverage: {:.2}\nMin (function): {}\nMax (function): {}\nCount: {}",
+            self.sum,
+            self.average,
+            self.min.map_or("N/A".into(), |min| format!("{:.2}", min)),
+            self.max.map_or("N/A".into(), |max| format!("{:.2}", max)),
+            self.tally.count
         )
     }
 }
 
+impl BasicSummary {
+    fn normalize(&mut self, by: f64) {
+        self.sum /= by;
+    }
+}
+
 impl Merge for BasicSummary {
     type Metric = BasicMetric;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, w| {
             s.sum += m.sum.unwrap_or(0.0);
-            s.average += m.average.unwrap_or(0.0);
-            s.min = s.min.min(m.min.unwrap_or(f64::MAX));
-            s.max = s.max.max(m.max.unwrap_or(f64::MIN));
+            s.average = running_mean(s.average, m.average.unwrap_or(0.0), w);
+            s.min = lowest(s.min, m.min);
+            s.max = highest(s.max, m.max);
+            s.file_min = lowest(s.file_min, m.sum);
+            s.file_max = highest(s.file_max, m.sum);
         })
     }
 }
@@ -310,25 +1976,61 @@ pub struct HalsteadSummary {
     purity_ratio: f64,
     bugs: f64,
     difficulty: f64,
-    estimated_program_lenght: f64,
+    length: f64,
+    estimated_program_length: f64,
     vocabulary: f64,
     level: f64,
-    count: usize,
+    effort: f64,
+    time: f64,
+    #[serde(flatten)]
+    tally: Tally,
+}
+
+impl HalsteadSummary {
+    fn normalize(&mut self, by: f64) {
+        for total in [
+            &mut self.n1,
+            &mut self.n2,
+            &mut self.vocabulary,
+            &mut self.volume,
+            &mut self.length,
+            &mut self.estimated_program_length,
+            &mut self.effort,
+            &mut self.time,
+            &mut self.bugs,
+        ] {
+            *total /= by;
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let parts = self.n1 + self.n2;
+        if reconciles(self.vocabulary, parts) {
+            return Vec::new();
+        }
+        vec![format!(
+            "Halstead vocabulary ({}) does not equal n1 + n2 ({})",
+            self.vocabulary, parts
+        )]
+    }
 }
 
 impl Merge for HalsteadSummary {
     type Metric = Halstead;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, _| {
             s.n1 += m.n1.unwrap_or(0.0);
             s.n2 += m.n2.unwrap_or(0.0);
             s.volume += m.volume.unwrap_or(0.0);
             s.bugs += m.bugs.unwrap_or(0.0);
             s.difficulty += m.difficulty.unwrap_or(0.0);
-            s.estimated_program_lenght += m.estimated_program_length.unwrap_or(0.0);
+            s.length += m.length.unwrap_or(0.0);
+            s.estimated_program_length += m.estimated_program_length.unwrap_or(0.0);
             s.vocabulary += m.vocabulary.unwrap_or(0.0);
             s.level += m.level.unwrap_or(0.0);
             s.purity_ratio += m.purity_ratio.unwrap_or(0.0);
+            s.effort += m.effort.unwrap_or(0.0);
+            s.time += m.time.unwrap_or(0.0);
         })
     }
 }
@@ -337,7 +2039,8 @@ impl Merge for HalsteadSummary {
 pub struct LocSummary {
     sloc: f64,
     ploc: f64,
-    count: usize,
+    #[serde(flatten)]
+    tally: Tally,
     lloc: f64,
     cloc: f64,
     blank: f64,
@@ -356,19 +2059,36 @@ pub struct LocSummary {
     lloc_max: f64,
     blank_min: f64,
     blank_max: f64,
+    /// Smallest and largest SLOC of a single file, among files reporting one.
+    sloc_file_min: Option<f64>,
+    sloc_file_max: Option<f64>,
+}
+
+impl LocSummary {
+    fn normalize(&mut self, by: f64) {
+        for total in [
+            &mut self.sloc,
+            &mut self.ploc,
+            &mut self.lloc,
+            &mut self.cloc,
+            &mut self.blank,
+        ] {
+            *total /= by;
+        }
+    }
 }
 
 impl Merge for LocSummary {
     type Metric = Loc;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, w| {
             s.sloc += m.sloc.unwrap_or(0.0);
             s.ploc += m.ploc.unwrap_or(0.0);
-            s.sloc_average += m.sloc_average.unwrap_or(0.0);
-            s.ploc_average += m.ploc_average.unwrap_or(0.0);
-            s.lloc_average += m.lloc_average.unwrap_or(0.0);
-            s.cloc_average += m.cloc_average.unwrap_or(0.0);
-            s.blank_average += m.blank_average.unwrap_or(0.0);
+            s.sloc_average = running_mean(s.sloc_average, m.sloc_average.unwrap_or(0.0), w);
+            s.ploc_average = running_mean(s.ploc_average, m.ploc_average.unwrap_or(0.0), w);
+            s.lloc_average = running_mean(s.lloc_average, m.lloc_average.unwrap_or(0.0), w);
+            s.cloc_average = running_mean(s.cloc_average, m.cloc_average.unwrap_or(0.0), w);
+            s.blank_average = running_mean(s.blank_average, m.blank_average.unwrap_or(0.0), w);
             s.sloc_min = if s.sloc_min == 0.0 {
                 m.sloc_min.unwrap_or(0.0)
             } else {
@@ -399,6 +2119,8 @@ impl Merge for LocSummary {
             s.lloc_max = s.lloc_max.max(m.lloc_max.unwrap_or(0.0));
             s.cloc_max = s.cloc_max.max(m.cloc_max.unwrap_or(0.0));
             s.blank_max = s.blank_max.max(m.blank_max.unwrap_or(0.0));
+            s.sloc_file_min = lowest(s.sloc_file_min, m.sloc);
+            s.sloc_file_max = highest(s.sloc_file_max, m.sloc);
         })
     }
 }
@@ -408,16 +2130,44 @@ pub struct NomSummary {
     functions: f64,
     closures: f64,
     total: f64,
-    count: usize,
+    functions_average: Option<f64>,
+    closures_average: Option<f64>,
+    average: Option<f64>,
+    functions_min: Option<f64>,
+    functions_max: Option<f64>,
+    closures_min: Option<f64>,
+    closures_max: Option<f64>,
+    #[serde(flatten)]
+    tally: Tally,
+}
+
+impl NomSummary {
+    fn validate(&self) -> Vec<String> {
+        let parts = self.functions + self.closures;
+        if reconciles(self.total, parts) {
+            return Vec::new();
+        }
+        vec![format!(
+            "NOM total ({}) does not equal functions + closures ({})",
+            self.total, parts
+        )]
+    }
 }
 
 impl Merge for NomSummary {
     type Metric = Nom;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, w| {
             s.functions += m.functions.unwrap_or(0.0);
             s.closures += m.closures.unwrap_or(0.0);
             s.total += m.total.unwrap_or(0.0);
+            s.functions_average = update_average(s.functions_average, w, m.functions_average);
+            s.closures_average = update_average(s.closures_average, w, m.closures_average);
+            s.average = update_average(s.average, w, m.average);
+            s.functions_min = lowest(s.functions_min, m.functions_min);
+            s.functions_max = highest(s.functions_max, m.functions_max);
+            s.closures_min = lowest(s.closures_min, m.closures_min);
+            s.closures_max = highest(s.closures_max, m.closures_max);
         })
     }
 }
@@ -427,16 +2177,30 @@ pub struct MiSummary {
     mi_original: f64,
     mi_sei: f64,
     mi_visual_studio: f64,
-    count: usize,
+    #[serde(flatten)]
+    tally: Tally,
+    #[serde(skip)]
+    variant: MiVariant,
+}
+
+impl MiSummary {
+    fn value(&self, variant: MiVariant) -> f64 {
+        match variant {
+            MiVariant::Original => self.mi_original,
+            MiVariant::Sei => self.mi_sei,
+            MiVariant::VisualStudio => self.mi_visual_studio,
+        }
+    }
 }
 
 impl Merge for MiSummary {
     type Metric = Mi;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
-            s.mi_original += m.mi_original.unwrap_or(0.0);
-            s.mi_sei += m.mi_sei.unwrap_or(0.0);
-            s.mi_visual_studio += m.mi_visual_studio.unwrap_or(0.0);
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, w| {
+            s.mi_original = running_mean(s.mi_original, m.mi_original.unwrap_or(0.0), w);
+            s.mi_sei = running_mean(s.mi_sei, m.mi_sei.unwrap_or(0.0), w);
+            s.mi_visual_studio =
+                running_mean(s.mi_visual_studio, m.mi_visual_studio.unwrap_or(0.0), w);
         })
     }
 }
@@ -446,16 +2210,59 @@ pub struct AbcSummary {
     assignments: f64,
     branches: f64,
     conditions: f64,
-    count: usize,
+    magnitude: f64,
+    assignments_average: Option<f64>,
+    branches_average: Option<f64>,
+    conditions_average: Option<f64>,
+    #[serde(flatten)]
+    tally: Tally,
+    #[serde(skip)]
+    average_tallies: AbcAverageTallies,
+}
+
+/// The files behind each ABC average, which only counts files that reported it.
+#[derive(Debug, Default, Clone, Copy)]
+struct AbcAverageTallies {
+    assignments: Tally,
+    branches: Tally,
+    conditions: Tally,
+}
+
+impl AbcSummary {
+    fn normalize(&mut self, by: f64) {
+        self.assignments /= by;
+        self.branches /= by;
+        self.conditions /= by;
+        self.magnitude /= by;
+    }
 }
 
 impl Merge for AbcSummary {
     type Metric = Abc;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, _| {
             s.assignments += m.assignments.unwrap_or(0.0);
             s.branches += m.branches.unwrap_or(0.0);
             s.conditions += m.conditions.unwrap_or(0.0);
+            s.magnitude += m.magnitude.unwrap_or(0.0);
+            merge_reported(
+                &mut s.assignments_average,
+                &mut s.average_tallies.assignments,
+                m.assignments_average,
+                weight,
+            );
+            merge_reported(
+                &mut s.branches_average,
+                &mut s.average_tallies.branches,
+                m.branches_average,
+                weight,
+            );
+            merge_reported(
+                &mut s.conditions_average,
+                &mut s.average_tallies.conditions,
+                m.conditions_average,
+                weight,
+            );
         })
     }
 }
@@ -465,13 +2272,14 @@ pub struct WmcSummary {
     pub classes: f64,
     pub interfaces: f64,
     pub total: f64,
-    pub count: usize,
+    #[serde(flatten)]
+    pub tally: Tally,
 }
 
 impl Merge for WmcSummary {
     type Metric = Wmc;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, _| {
             s.classes += m.classes.unwrap_or(0.0);
             s.interfaces += m.interfaces.unwrap_or(0.0);
             s.total += m.total.unwrap_or(0.0);
@@ -479,23 +2287,60 @@ impl Merge for WmcSummary {
     }
 }
 
+/// The files behind each per-kind average, which only counts files that reported it.
+#[derive(Debug, Default, Clone, Copy)]
+struct AverageTallies {
+    classes: Tally,
+    interfaces: Tally,
+}
+
+/// Folds a reported average into `average`. A file that doesn't report it neither moves the
+/// average nor adds to its weight.
+fn merge_reported(average: &mut Option<f64>, tally: &mut Tally, new: Option<f64>, weight: Weight) {
+    if let Some(value) = new {
+        *average = Some(running_mean(
+            average.unwrap_or(value),
+            value,
+            tally.share(weight),
+        ));
+        tally.add(weight);
+    }
+}
+
 #[derive(Debug, Serialize, Default, Clone, Copy)]
 pub struct NpmSummary {
     pub classes: f64,
     pub interfaces: f64,
     pub class_methods: f64,
     pub total: f64,
-    pub count: usize,
+    pub classes_average: Option<f64>,
+    pub interfaces_average: Option<f64>,
+    #[serde(flatten)]
+    pub tally: Tally,
+    #[serde(skip)]
+    average_tallies: AverageTallies,
 }
 
 impl Merge for NpmSummary {
     type Metric = Npm;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, _| {
             s.classes += m.classes.unwrap_or(0.0);
             s.interfaces += m.interfaces.unwrap_or(0.0);
             s.class_methods += m.class_methods.unwrap_or(0.0);
             s.total += m.total.unwrap_or(0.0);
+            merge_reported(
+                &mut s.classes_average,
+                &mut s.average_tallies.classes,
+                m.classes_average.flatten(),
+                weight,
+            );
+            merge_reported(
+                &mut s.interfaces_average,
+                &mut s.average_tallies.interfaces,
+                m.interfaces_average.flatten(),
+                weight,
+            );
         })
     }
 }
@@ -505,158 +2350,1253 @@ pub struct NpaSummary {
     pub classes: f64,
     pub interfaces: f64,
     pub total: f64,
-    pub count: usize,
+    pub classes_average: Option<f64>,
+    pub interfaces_average: Option<f64>,
+    #[serde(flatten)]
+    pub tally: Tally,
+    #[serde(skip)]
+    average_tallies: AverageTallies,
 }
 
 impl Merge for NpaSummary {
     type Metric = Npa;
-    fn merge(current: Option<Self>, metric: &Option<Self::Metric>) -> Option<Self> {
-        merge_with(current, metric, |s, m| {
+    fn merge(current: Option<Self>, metric: &Option<Self::Metric>, weight: Weight) -> Option<Self> {
+        merge_with(current, metric, weight, |s, m, _| {
             s.classes += m.classes.unwrap_or(0.0);
             s.interfaces += m.interfaces.unwrap_or(0.0);
             s.total += m.total.unwrap_or(0.0);
+            merge_reported(
+                &mut s.classes_average,
+                &mut s.average_tallies.classes,
+                m.classes_average.flatten(),
+                weight,
+            );
+            merge_reported(
+                &mut s.interfaces_average,
+                &mut s.average_tallies.interfaces,
+                m.interfaces_average.flatten(),
+                weight,
+            );
+        })
+    }
+}
+
+/// A configured custom metric, summed and averaged over the files that report it.
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct CustomSummary {
+    pub label: String,
+    pub aggregate: Aggregate,
+    pub sum: f64,
+    pub average: f64,
+    #[serde(flatten)]
+    pub tally: Tally,
+}
+
+impl CustomSummary {
+    fn new(metric: &CustomMetric) -> Self {
+        Self {
+            label: metric.label.clone(),
+            aggregate: metric.aggregate,
+            ..Self::default()
+        }
+    }
+
+    fn add(&mut self, value: f64, weight: Weight) {
+        self.average = running_mean(self.average, value, self.tally.share(weight));
+        self.sum += value;
+        self.tally.add(weight);
+    }
+
+    fn normalize(&mut self, functions: f64) {
+        self.sum /= functions;
+    }
+}
+
+impl Detailed for CustomSummary {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        let mut details = by_value_mode(
+            fmt,
+            vec![("Sum".into(), fmt.metric(None, Some(self.sum)))],
+            vec![("Average".into(), fmt.metric(None, Some(self.average)))],
+            vec![("Count".into(), fmt.count(self.tally.count))],
+        );
+        if self.aggregate == Aggregate::Average && fmt.values == ValueMode::Both {
+            details.swap(0, 1);
+        }
+        details
+    }
+
+    fn headline(&self) -> Option<f64> {
+        (self.tally.count > 0).then_some(match self.aggregate {
+            Aggregate::Sum => self.sum,
+            Aggregate::Average => self.average,
         })
     }
 }
 
+impl Detailed for NestingSummary {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        vec![
+            (
+                "Max".into(),
+                fmt.metric(Some("nesting.max"), Some(self.max)),
+            ),
+            (
+                "Average".into(),
+                fmt.metric(Some("nesting.average"), Some(self.average)),
+            ),
+            ("Spaces".into(), fmt.count(self.count)),
+        ]
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.max)
+    }
+}
+
+impl Detailed for KindSummary {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        let mut counts: Vec<_> = self.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let total = self.counts.values().sum::<usize>() as f64;
+        counts
+            .into_iter()
+            .map(|(kind, count)| {
+                let value = if fmt.percentages {
+                    fmt.breakdown(*count as f64, total)
+                } else {
+                    fmt.count(*count)
+                };
+                (kind.clone(), value)
+            })
+            .collect()
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.counts.values().sum::<usize>() as f64)
+    }
+}
+
+/// One extension's totals next to the totals of every extension, which are the denominators
+/// when breakdowns are shown as percentages.
+#[derive(Debug, Clone, Copy)]
+struct ExtensionBreakdown {
+    summary: ExtensionSummary,
+    total: ExtensionSummary,
+}
+
+impl Detailed for ExtensionBreakdown {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        let (s, t) = (&self.summary, &self.total);
+        let files = if fmt.percentages {
+            fmt.breakdown(s.files as f64, t.files as f64)
+        } else {
+            fmt.count(s.files)
+        };
+        vec![
+            ("SLOC".into(), fmt.breakdown(s.sloc, t.sloc)),
+            ("Files".into(), files),
+            ("Functions".into(), fmt.breakdown(s.functions, t.functions)),
+            (
+                "Cyclomatic Sum".into(),
+                fmt.breakdown(s.cyclomatic, t.cyclomatic),
+            ),
+            (
+                "Cognitive Sum".into(),
+                fmt.breakdown(s.cognitive, t.cognitive),
+            ),
+        ]
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.summary.sloc)
+    }
+}
+
 impl Detailed for MetricValuesSummary {
-    fn details(&self) -> Vec<(String, String)> {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        by_value_mode(
+            fmt,
+            vec![
+                (
+                    "Total Functions".into(),
+                    fmt.metric(Some("nargs.total_functions"), self.total_functions),
+                ),
+                (
+                    "Total Closures".into(),
+                    fmt.metric(Some("nargs.total_closures"), self.total_closures),
+                ),
+                ("Total".into(), fmt.metric(Some("nargs.total"), self.total)),
+            ],
+            vec![
+                (
+                    "Avg Functions".into(),
+                    fmt.metric(Some("nargs.average_functions"), self.average_functions),
+                ),
+                (
+                    "Avg Closures".into(),
+                    fmt.metric(Some("nargs.average_closures"), self.average_closures),
+                ),
+                (
+                    "Average".into(),
+                    fmt.metric(Some("nargs.average"), self.average),
+                ),
+            ],
+            vec![
+                (
+                    "Min Functions".into(),
+                    fmt.metric(Some("nargs.functions_min"), self.functions_min),
+                ),
+                (
+                    "Max Functions".into(),
+                    fmt.metric(Some("nargs.functions_max"), self.functions_max),
+                ),
+                (
+                    "Min Closures".into(),
+                    fmt.metric(Some("nargs.closures_min"), self.closures_min),
+                ),
+                (
+                    "Max Closures".into(),
+                    fmt.metric(Some("nargs.closures_max"), self.closures_max),
+                ),
+                (
+                    "Total Min (file)".into(),
+                    fmt.metric(Some("nargs.file_min"), self.file_min),
+                ),
+                (
+                    "Total Max (file)".into(),
+                    fmt.metric(Some("nargs.file_max"), self.file_max),
+                ),
+                ("Count".into(), fmt.count(self.tally.count)),
+            ],
+        )
+    }
+
+    fn headline(&self) -> Option<f64> {
+        self.total
+    }
+}
+
+impl Detailed for BasicSummary {
+    fn details(&self, family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        let key = |field: &str| family.map(|family| format!("{}.{}", family, field));
+        by_value_mode(
+            fmt,
+            vec![(
+                "Sum".into(),
+                fmt.metric(key("sum").as_deref(), Some(self.sum)),
+            )],
+            vec![(
+                "Average".into(),
+                fmt.metric(key("average").as_deref(), Some(self.average)),
+            )],
+            vec![
+                (
+                    "Min (function)".into(),
+                    fmt.metric(key("min").as_deref(), self.min),
+                ),
+                (
+                    "Max (function)".into(),
+                    fmt.metric(key("max").as_deref(), self.max),
+                ),
+                (
+                    "Min (file)".into(),
+                    fmt.metric(key("file_min").as_deref(), self.file_min),
+                ),
+                (
+                    "Max (file)".into(),
+                    fmt.metric(key("file_max").as_deref(), self.file_max),
+                ),
+                ("Count".into(), fmt.count(self.tally.count)),
+            ],
+        )
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.sum)
+    }
+}
+
+const SECONDS_PER_HOUR: f64 = 3600.0;
+
+impl Detailed for HalsteadSummary {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
         vec![
             (
-                "Total Functions".into(),
-                self.total_functions.map_or("N/A".into(), |v| v.to_string()),
+                "Volume".into(),
+                fmt.metric(Some("halstead.volume"), Some(self.volume)),
             ),
+            ("n1".into(), fmt.metric(Some("halstead.n1"), Some(self.n1))),
+            ("n2".into(), fmt.metric(Some("halstead.n2"), Some(self.n2))),
             (
-                "Total Closures".into(),
-                self.total_closures.map_or("N/A".into(), |v| v.to_string()),
+                "purity_ratio".into(),
+                fmt.metric(Some("halstead.purity_ratio"), Some(self.purity_ratio)),
             ),
             (
-                "Avg Functions".into(),
-                self.average_functions
-                    .map_or("N/A".into(), |v| format!("{:.2}", v)),
+                "Bugs".into(),
+                fmt.metric(Some("halstead.bugs"), Some(self.bugs)),
             ),
             (
-                "Avg Closures".into(),
-                self.average_closures
-                    .map_or("N/A".into(), |v| format!("{:.2}", v)),
+                "Length".into(),
+                fmt.metric(Some("halstead.length"), Some(self.length)),
             ),
             (
-                "Total".into(),
-                self.total.map_or("N/A".into(), |v| v.to_string()),
+                "Estimated Program Length".into(),
+                fmt.metric(
+                    Some("halstead.estimated_program_length"),
+                    Some(self.estimated_program_length),
+                ),
             ),
             (
-                "Average".into(),
-                self.average.map_or("N/A".into(), |v| format!("{:.2}", v)),
+                "Estimated / Actual Length".into(),
+                fmt.metric(
+                    Some("halstead.purity_ratio"),
+                    (self.length > 0.0).then(|| self.estimated_program_length / self.length),
+                ),
+            ),
+            (
+                "Vocabulary".into(),
+                fmt.metric(Some("halstead.vocabulary"), Some(self.vocabulary)),
+            ),
+            (
+                "Difficulty".into(),
+                fmt.metric(Some("halstead.difficulty"), Some(self.difficulty)),
             ),
             (
-                "Min Functions".into(),
-                self.functions_min.map_or("N/A".into(), |v| v.to_string()),
+                "Level".into(),
+                fmt.metric(Some("halstead.level"), Some(self.level)),
             ),
             (
-                "Max Functions".into(),
-                self.functions_max.map_or("N/A".into(), |v| v.to_string()),
+                "Effort".into(),
+                fmt.metric(Some("halstead.effort"), Some(self.effort)),
             ),
             (
-                "Min Closures".into(),
-                self.closures_min.map_or("N/A".into(), |v| v.to_string()),
+                "Time (s)".into(),
+                fmt.metric(Some("halstead.time"), Some(self.time)),
             ),
             (
-                "Max Closures".into(),
-                self.closures_max.map_or("N/A".into(), |v| v.to_string()),
+                "Estimated Effort (hours, approx.)".into(),
+                fmt.with_decimals(1).float(self.time / SECONDS_PER_HOUR),
             ),
-            ("Count".into(), self.count.to_string()),
+            ("Count".into(), fmt.count(self.tally.count)),
         ]
     }
-}
 
-impl Detailed for BasicSummary {
-    fn details(&self) -> Vec<(String, String)> {
-        vec![
-            ("Sum".into(), format!("{:.2}", self.sum)),
-            ("Average".into(), format!("{:.2}", self.average)),
-            ("Min".into(), format!("{:.2}", self.min)),
-            ("Max".into(), format!("{:.2}", self.max)),
-            ("Count".into(), self.count.to_string()),
-        ]
+    fn headline(&self) -> Option<f64> {
+        Some(self.volume)
     }
 }
 
-impl Detailed for HalsteadSummary {
-    fn details(&self) -> Vec<(String, String)> {
-        vec![
-            ("n1".into(), format!("{:.2}", self.n1)),
-            ("n2".into(), format!("{:.2}", self.n2)),
-            ("purity_ratio".into(), format!("{:.2}", self.purity_ratio)),
-            ("Bugs".into(), format!("{:.2}", self.bugs)),
-            (
-                "Estimated Program Lenght".into(),
-                format!("{:.2}", self.estimated_program_lenght),
-            ),
-            ("Vocabulary".into(), format!("{:.2}", self.vocabulary)),
-            ("Difficulty".into(), format!("{:.2}", self.difficulty)),
-            ("Level".into(), format!("{:.2}", self.level)),
-            ("Volume".into(), format!("{:.2}", self.volume)),
-            ("Count".into(), self.count.to_string()),
-        ]
+impl Detailed for LocSummary {
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        by_value_mode(
+            fmt,
+            vec![
+                ("SLOC".into(), fmt.metric(Some("loc.sloc"), Some(self.sloc))),
+                ("PLOC".into(), fmt.metric(Some("loc.ploc"), Some(self.ploc))),
+                ("LLOC".into(), fmt.metric(Some("loc.lloc"), Some(self.lloc))),
+                ("CLOC".into(), fmt.metric(Some("loc.cloc"), Some(self.cloc))),
+                (
+                    "Blank".into(),
+                    fmt.metric(Some("loc.blank"), Some(self.blank)),
+                ),
+            ],
+            vec![
+                (
+                    "SLOC Avg".into(),
+                    fmt.metric(Some("loc.sloc_average"), Some(self.sloc_average)),
+                ),
+                (
+                    "PLOC Avg".into(),
+                    fmt.metric(Some("loc.ploc_average"), Some(self.ploc_average)),
+                ),
+                (
+                    "LLOC Avg".into(),
+                    fmt.metric(Some("loc.lloc_average"), Some(self.lloc_average)),
+                ),
+                (
+                    "CLOC Avg".into(),
+                    fmt.metric(Some("loc.cloc_average"), Some(self.cloc_average)),
+                ),
+                (
+                    "Blank Avg".into(),
+                    fmt.metric(Some("loc.blank_average"), Some(self.blank_average)),
+                ),
+            ],
+            vec![
+                (
+                    "SLOC Min (function)".into(),
+                    fmt.metric(Some("loc.sloc_min"), Some(self.sloc_min)),
+                ),
+                (
+                    "SLOC Max (function)".into(),
+                    fmt.metric(Some("loc.sloc_max"), Some(self.sloc_max)),
+                ),
+                (
+                    "CLOC Min (function)".into(),
+                    fmt.metric(Some("loc.cloc_min"), Some(self.cloc_min)),
+                ),
+                (
+                    "CLOC Max (function)".into(),
+                    fmt.metric(Some("loc.cloc_max"), Some(self.cloc_max)),
+                ),
+                (
+                    "PLOC Min (function)".into(),
+                    fmt.metric(Some("loc.ploc_min"), Some(self.ploc_min)),
+                ),
+                (
+                    "PLOC Max (function)".into(),
+                    fmt.metric(Some("loc.ploc_max"), Some(self.ploc_max)),
+                ),
+                (
+                    "LLOC Min (function)".into(),
+                    fmt.metric(Some("loc.lloc_min"), Some(self.lloc_min)),
+                ),
+                (
+                    "LLOC Max (function)".into(),
+                    fmt.metric(Some("loc.lloc_max"), Some(self.lloc_max)),
+                ),
+                (
+                    "Blank Min (function)".into(),
+                    fmt.metric(Some("loc.blank_min"), Some(self.blank_min)),
+                ),
+                (
+                    "Blank Max (function)".into(),
+                    fmt.metric(Some("loc.blank_max"), Some(self.blank_max)),
+                ),
+                (
+                    "SLOC Min (file)".into(),
+                    fmt.metric(Some("loc.sloc_file_min"), self.sloc_file_min),
+                ),
+                (
+                    "SLOC Max (file)".into(),
+                    fmt.metric(Some("loc.sloc_file_max"), self.sloc_file_max),
+                ),
+            ],
+        )
     }
-}
 
-impl Detailed for LocSummary {
-    fn details(&self) -> Vec<(String, String)> {
-        vec![
-            ("SLOC".into(), format!("{:.2}", self.sloc)),
-            ("PLOC".into(), format!("{:.2}", self.ploc)),
-            ("LLOC".into(), format!("{:.2}", self.lloc)),
-            ("CLOC".into(), format!("{:.2}", self.cloc)),
-            ("Blank".into(), format!("{:.2}", self.blank)),
-            ("SLOC Avg".into(), format!("{:.2}", self.sloc_average)),
-            ("PLOC Avg".into(), format!("{:.2}", self.ploc_average)),
-            ("LLOC Avg".into(), format!("{:.2}", self.lloc_average)),
-            ("CLOC Avg".into(), format!("{:.2}", self.cloc_average)),
-            ("Blank Avg".into(), format!("{:.2}", self.blank_average)),
-            ("SLOC Min".into(), format!("{:.2}", self.sloc_min)),
-            ("SLOC Max".into(), format!("{:.2}", self.sloc_max)),
-            ("CLOC Min".into(), format!("{:.2}", self.cloc_min)),
-            ("CLOC Max".into(), format!("{:.2}", self.cloc_max)),
-            ("PLOC Min".into(), format!("{:.2}", self.ploc_min)),
-            ("PLOC Max".into(), format!("{:.2}", self.ploc_max)),
-            ("LLOC Min".into(), format!("{:.2}", self.lloc_min)),
-            ("LLOC Max".into(), format!("{:.2}", self.lloc_max)),
-            ("Blank Min".into(), format!("{:.2}", self.blank_min)),
-            ("Blank Max".into(), format!("{:.2}", self.blank_max)),
-        ]
+    fn headline(&self) -> Option<f64> {
+        Some(self.sloc)
     }
 }
 
 impl Detailed for NomSummary {
-    fn details(&self) -> Vec<(String, String)> {
-        vec![
-            ("Functions".into(), format!("{:.2}", self.functions)),
-            ("Closures".into(), format!("{:.2}", self.closures)),
-            ("Total".into(), format!("{:.2}", self.total)),
-            ("Count".into(), self.count.to_string()),
-        ]
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        by_value_mode(
+            fmt,
+            vec![
+                (
+                    "Functions".into(),
+                    fmt.metric(Some("nom.functions"), Some(self.functions)),
+                ),
+                (
+                    "Closures".into(),
+                    fmt.metric(Some("nom.closures"), Some(self.closures)),
+                ),
+                (
+                    "Total".into(),
+                    fmt.metric(Some("nom.total"), Some(self.total)),
+                ),
+            ],
+            vec![
+                (
+                    "Avg Functions".into(),
+                    fmt.metric(Some("nom.functions_average"), self.functions_average),
+                ),
+                (
+                    "Avg Closures".into(),
+                    fmt.metric(Some("nom.closures_average"), self.closures_average),
+                ),
+                (
+                    "Average".into(),
+                    fmt.metric(Some("nom.average"), self.average),
+                ),
+            ],
+            vec![
+                (
+                    "Min Functions".into(),
+                    fmt.metric(Some("nom.functions_min"), self.functions_min),
+                ),
+                (
+                    "Max Functions".into(),
+                    fmt.metric(Some("nom.functions_max"), self.functions_max),
+                ),
+                (
+                    "Min Closures".into(),
+                    fmt.metric(Some("nom.closures_min"), self.closures_min),
+                ),
+                (
+                    "Max Closures".into(),
+                    fmt.metric(Some("nom.closures_max"), self.closures_max),
+                ),
+                ("Count".into(), fmt.count(self.tally.count)),
+            ],
+        )
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.total)
     }
 }
 
 impl Detailed for MiSummary {
-    fn details(&self) -> Vec<(String, String)> {
-        vec![
-            ("MI Original".into(), format!("{:.2}", self.mi_original)),
-            ("MI SEI".into(), format!("{:.2}", self.mi_sei)),
-            ("MI VS".into(), format!("{:.2}", self.mi_visual_studio)),
-            ("Count".into(), self.count.to_string()),
-        ]
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        // The headline variant comes first, as collapsed rows show the first detail.
+        let others = MiVariant::ALL
+            .into_iter()
+            .filter(|variant| *variant != self.variant);
+        std::iter::once(self.variant)
+            .chain(others)
+            .map(|variant| {
+                let value = fmt.metric(Some(variant.key()), Some(self.value(variant)));
+                (variant.label().into(), value)
+            })
+            .chain([("Count".into(), fmt.count(self.tally.count))])
+            .collect()
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.value(self.variant))
     }
 }
 
 impl Detailed for AbcSummary {
-    fn details(&self) -> Vec<(String, String)> {
-        vec![
-            ("Assignments".into(), format!("{:.2}", self.assignments)),
-            ("Branches".into(), format!("{:.2}", self.branches)),
-            ("Conditions".into(), format!("{:.2}", self.conditions)),
-            ("Count".into(), self.count.to_string()),
+    fn details(&self, _family: Option<&str>, fmt: &NumberFormat) -> Vec<(String, String)> {
+        let magnitude = (
+            "Magnitude".into(),
+            fmt.metric(Some("abc.magnitude"), Some(self.magnitude)),
+        );
+        let rest = by_value_mode(
+            fmt,
+            vec![
+                (
+                    "Assignments".into(),
+                    fmt.metric(Some("abc.assignments"), Some(self.assignments)),
+                ),
+                (
+                    "Branches".into(),
+                    fmt.metric(Some("abc.branches"), Some(self.branches)),
+                ),
+                (
+                    "Conditions".into(),
+                    fmt.metric(Some("abc.conditions"), Some(self.conditions)),
+                ),
+            ],
+            vec![
+                (
+                    "Assignments Average".into(),
+                    fmt.metric(Some("abc.assignments_average"), self.assignments_average),
+                ),
+                (
+                    "Branches Average".into(),
+                    fmt.metric(Some("abc.branches_average"), self.branches_average),
+                ),
+                (
+                    "Conditions Average".into(),
+                    fmt.metric(Some("abc.conditions_average"), self.conditions_average),
+                ),
+            ],
+            vec![("Count".into(), fmt.count(self.tally.count))],
+        );
+        std::iter::once(magnitude).chain(rest).collect()
+    }
+
+    fn headline(&self) -> Option<f64> {
+        Some(self.magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(name: &str, sloc: f64, cyclomatic: f64) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "start_line": 1,
+            "end_line": sloc as u32,
+            "kind": "unit",
+            "spaces": [],
+            "metrics": {
+                "nargs": null, "nexits": null, "cognitive": null,
+                "cyclomatic": { "sum": cyclomatic, "average": cyclomatic, "min": 1.0, "max": cyclomatic },
+                "halstead": null,
+                "loc": { "sloc": sloc, "ploc": sloc, "lloc": sloc, "cloc": 0.0, "blank": 0.0 },
+                "nom": null, "mi": null, "abc": null, "wmc": null, "npm": null, "npa": null
+            }
+        })
+    }
+
+    fn project(units: Vec<serde_json::Value>) -> serde_json::Value {
+        let mut project = unit("project", 1000.0, 1000.0);
+        project["spaces"] = serde_json::json!([{
+            "name": "src",
+            "start_line": 1,
+            "end_line": 1,
+            "kind": "directory",
+            "spaces": units,
+            "metrics": null
+        }]);
+        project
+    }
+
+    fn parse(values: Vec<serde_json::Value>) -> Vec<JsonData> {
+        values
+            .into_iter()
+            .map(|value| serde_json::from_value(value).unwrap())
+            .collect()
+    }
+
+    fn totals(data: Vec<JsonData>) -> (usize, BTreeMap<String, f64>) {
+        let summary = MetricsSummary::summarize(&merge_layouts(data), &SummaryOptions::default());
+        (summary.files(), summary.values())
+    }
+
+    #[test]
+    fn per_file_layout_is_summed() {
+        let (files, values) = totals(parse(vec![
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/b.rs", 20.0, 4.0),
+        ]));
+        assert_eq!(files, 2);
+        assert_eq!(values["loc.sloc"], 30.0);
+        assert_eq!(values["cyclomatic.sum"], 7.0);
+    }
+
+    #[test]
+    fn whole_project_layout_is_split_into_files() {
+        let (files, values) = totals(parse(vec![project(vec![
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/b.rs", 20.0, 4.0),
+        ])]));
+        assert_eq!(files, 2);
+        assert_eq!(values["loc.sloc"], 30.0);
+        assert_eq!(values["cyclomatic.sum"], 7.0);
+    }
+
+    #[test]
+    fn mixed_layouts_are_not_double_counted() {
+        let (files, values) = totals(parse(vec![
+            unit("src/a.rs", 10.0, 3.0),
+            project(vec![
+                unit("./src/a.rs", 10.0, 3.0),
+                unit("src/b.rs", 20.0, 4.0),
+            ]),
+        ]));
+        assert_eq!(files, 2);
+        assert_eq!(values["loc.sloc"], 30.0);
+        assert_eq!(values["cyclomatic.sum"], 7.0);
+    }
+
+    #[test]
+    fn ndjson_records_of_one_file_are_all_summarized() {
+        let dir = std::env::temp_dir().join(format!("rca-tui-records-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let lines: Vec<String> = [
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/a.rs", 20.0, 4.0),
+            unit("src/b.rs", 5.0, 1.0),
+        ]
+        .iter()
+        .map(|value| value.to_string())
+        .collect();
+        fs::write(dir.join("batch.ndjson"), lines.join("\n")).unwrap();
+        let options = SummaryOptions::default();
+        let scan = scan_directory(&dir, &options, &AtomicBool::new(false));
+        fs::remove_dir_all(&dir).unwrap();
+        let summary = MetricsSummary::summarize(&scan.unwrap().data, &options);
+        assert_eq!(summary.files(), 2);
+        assert_eq!(summary.values()["loc.sloc"], 35.0);
+        assert_eq!(summary.extensions[".rs"].files, 2);
+    }
+
+    #[test]
+    fn functions_inside_a_file_are_not_mistaken_for_a_project() {
+        let mut file = unit("src/a.rs", 10.0, 3.0);
+        file["spaces"] = serde_json::json!([{
+            "name": "main",
+            "start_line": 1,
+            "end_line": 5,
+            "kind": "function",
+            "spaces": [],
+            "metrics": null
+        }]);
+        let data = merge_layouts(parse(vec![file]));
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].spaces.len(), 1);
+    }
+
+    #[test]
+    fn files_are_grouped_below_their_shared_directory() {
+        let data = parse(vec![
+            unit("./repo/crates/a/src/lib.rs", 10.0, 3.0),
+            unit("repo/crates/a/src/main.rs", 5.0, 1.0),
+            unit("repo/crates/b/lib.rs", 20.0, 4.0),
+            unit("repo/crates/build.rs", 1.0, 1.0),
+        ]);
+        let groups = group_by_directory(&data);
+        let sizes: Vec<(&str, usize)> = groups
+            .iter()
+            .map(|(name, files)| (name.as_str(), files.len()))
+            .collect();
+        assert_eq!(sizes, vec![(".", 1), ("a/", 2), ("b/", 1)]);
+    }
+
+    #[test]
+    fn groups_keep_the_files_behind_their_summary() {
+        let mut mi = unit("repo/b/lib.rs", 20.0, 4.0);
+        mi["metrics"]["mi"] =
+            serde_json::json!({ "mi_original": 80.0, "mi_sei": 60.0, "mi_visual_studio": 40.0 });
+        let data = parse(vec![
+            unit("repo/a/lib.rs", 10.0, 3.0),
+            unit("repo/a/main.rs", 5.0, 1.0),
+            mi,
+        ]);
+        let options = SummaryOptions {
+            mi_variant: MiVariant::Sei,
+            ..SummaryOptions::default()
+        };
+        let groups = SummaryGroup::by_directory(&data, &options);
+        for group in &groups {
+            assert_eq!(group.summary.files(), group.files.len());
+            let sum: f64 = group
+                .files
+                .iter()
+                .filter_map(|f| f.get("cyclomatic.sum"))
+                .sum();
+            assert_eq!(Some(sum), group.summary.headline("cyclomatic.sum"));
+        }
+        let b = &groups[1].files[0];
+        assert_eq!(
+            (b.name.as_str(), b.get("loc.sloc")),
+            ("repo/b/lib.rs", Some(20.0))
+        );
+        assert_eq!(b.get("mi.mi_sei"), Some(60.0));
+        assert_eq!(b.get("mi.mi_original"), None);
+    }
+
+    #[test]
+    fn file_lists_skip_comments_and_reject_empty_lists() {
+        let dir = std::env::temp_dir().join(format!("rca-tui-list-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let metrics = dir.join("a.json");
+        fs::write(&metrics, "{}").unwrap();
+        let list = dir.join("list.txt");
+        fs::write(&list, format!("# changed\n\n{}\n", metrics.display())).unwrap();
+        assert_eq!(read_file_list(&list).unwrap(), [metrics]);
+
+        fs::write(&list, "# nothing changed\n\n").unwrap();
+        let err = read_file_list(&list).unwrap_err().to_string();
+        assert!(err.ends_with("no files listed"), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ignored_files_are_counted_in_the_same_walk() {
+        let dir = std::env::temp_dir().join(format!("rca-tui-ignore-{}", std::process::id()));
+        for sub in ["build", "sub"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        fs::write(dir.join(".gitignore"), "build/\n*.skip.json\n").unwrap();
+        fs::write(dir.join("sub").join(".gitignore"), "!kept.skip.json\n").unwrap();
+        for file in [
+            "a.json",
+            "build/b.json",
+            "c.skip.json",
+            "sub/kept.skip.json",
+        ] {
+            fs::write(dir.join(file), "{}").unwrap();
+        }
+
+        let (files, ignored) = walk_metrics_files(&dir, &SummaryOptions::default());
+        assert_eq!(
+            files,
+            [dir.join("a.json"), dir.join("sub").join("kept.skip.json")]
+        );
+        assert_eq!(ignored, 2);
+
+        let everything = SummaryOptions {
+            respect_ignore: false,
+            ..SummaryOptions::default()
+        };
+        let (files, ignored) = walk_metrics_files(&dir, &everything);
+        assert_eq!((files.len(), ignored), (4, 0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loops_are_skipped_when_following_links() {
+        let dir = std::env::temp_dir().join(format!("rca-tui-loop-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("a.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("sub").join("back")).unwrap();
+        for respect_ignore in [true, false] {
+            let options = SummaryOptions {
+                follow_symlinks: true,
+                respect_ignore,
+                ..SummaryOptions::default()
+            };
+            let (files, _) = walk_metrics_files(&dir, &options);
+            assert_eq!(files, [dir.join("sub").join("a.json")]);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn diff_keeps_values_missing_on_either_side() {
+        let summary = summary_of(
+            vec![unit("src/a.rs", 10.0, 3.0)],
+            &SummaryOptions::default(),
+        );
+        let baseline = BTreeMap::from([
+            ("cyclomatic.sum".to_owned(), 5.0),
+            ("removed.metric".to_owned(), 1.0),
+        ]);
+        let deltas = summary.diff(&baseline);
+        let find = |metric: &str| deltas.iter().find(|d| d.metric == metric).unwrap();
+        let sum = find("cyclomatic.sum");
+        assert_eq!(
+            (sum.baseline, sum.current, sum.delta),
+            (Some(5.0), Some(3.0), Some(-2.0))
+        );
+        let removed = find("removed.metric");
+        assert_eq!((removed.current, removed.delta), (None, None));
+        let added = find("loc.sloc");
+        assert_eq!((added.baseline, added.current), (None, Some(10.0)));
+    }
+
+    #[test]
+    fn diff_csv_quotes_metric_names_and_marks_missing_values() {
+        let deltas = [
+            MetricDelta::new("custom.\"a,b\"", Some(1.0), Some(1.5)),
+            MetricDelta::new("loc.sloc", None, Some(10.0)),
+        ];
+        assert_eq!(
+            diff_to_csv(&deltas),
+            "metric,baseline,current,delta\n\
+             \"custom.\"\"a,b\"\"\",1,1.5,0.5\n\
+             loc.sloc,N/A,10,N/A\n"
+        );
+    }
+
+    #[test]
+    fn duplicate_spaces_share_name_lines_and_kind() {
+        let space = |name: &str, start: u32, end: u32, kind: &str| {
+            serde_json::json!({
+                "name": name, "start_line": start, "end_line": end, "kind": kind,
+                "spaces": [], "metrics": null
+            })
+        };
+        let mut file = unit("src/a.rs", 20.0, 3.0);
+        let mut outer = space("run", 1, 10, "function");
+        outer["spaces"] = serde_json::json!([space("<anonymous>", 2, 3, "closure")]);
+        file["spaces"] = serde_json::json!([
+            outer,
+            space("run", 1, 10, "function"),
+            space("run", 1, 10, "impl"),
+            space("run", 1, 11, "function"),
+            space("<anonymous>", 2, 3, "closure"),
+        ]);
+        let mut data = parse(vec![file]);
+        // The same span under another kind or with other lines is kept; a repeat is dropped
+        // wherever it is nested.
+        assert_eq!(dedup_spaces(&mut data), 2);
+        let kept: Vec<_> = data[0]
+            .spaces
+            .iter()
+            .map(|space| (space.kind.as_str(), space.end_line, space.spaces.len()))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                ("function", 10, 0),
+                ("impl", 10, 0),
+                ("function", 11, 0),
+                ("closure", 3, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn averages_hold_at_large_weights() {
+        let huge = Tally {
+            count: 1,
+            sloc: 1e300,
+        };
+        assert_eq!(
+            running_mean(10.0, 20.0, huge.share(Weight::Sloc(1e300))),
+            15.0
+        );
+        let past_exact = Tally {
+            count: 1 << 60,
+            sloc: 0.0,
+        };
+        assert_eq!(running_mean(7.5, 7.5, past_exact.share(Weight::File)), 7.5);
+        let mut loc = LocSummary {
+            tally: Tally {
+                count: usize::MAX,
+                sloc: 0.0,
+            },
+            ..LocSummary::default()
+        };
+        loc.tally.add(Weight::File);
+        assert_eq!(loc.tally.count, usize::MAX);
+    }
+
+    #[test]
+    fn absent_and_null_averages_do_not_contribute() {
+        let absent = r#"{ "classes": 1.0, "total": 2.0 }"#;
+        let null = r#"{ "classes": 1.0, "total": 2.0, "classes_average": null }"#;
+        let value = r#"{ "classes": 1.0, "total": 2.0, "classes_average": 4.0 }"#;
+
+        let npm = |json: &str| Some(serde_json::from_str::<Npm>(json).unwrap());
+        assert_eq!(npm(absent).unwrap().classes_average, None);
+        assert_eq!(npm(null).unwrap().classes_average, Some(None));
+        assert_eq!(npm(value).unwrap().classes_average, Some(Some(4.0)));
+
+        let summary = [absent, null, value, absent]
+            .into_iter()
+            .fold(None, |s, json| {
+                NpmSummary::merge(s, &npm(json), Weight::Sloc(10.0))
+            })
+            .unwrap();
+        assert_eq!(summary.tally.count, 4);
+        assert_eq!(summary.classes, 4.0);
+        assert_eq!(summary.classes_average, Some(4.0));
+        assert_eq!(summary.interfaces_average, None);
+        let only_null = NpmSummary::merge(None, &npm(null), Weight::Sloc(10.0)).unwrap();
+        assert_eq!(only_null.classes_average, None);
+
+        let npa = |json: &str| Some(serde_json::from_str::<Npa>(json).unwrap());
+        let summary = [value, null, r#"{ "classes_average": 8.0 }"#]
+            .into_iter()
+            .fold(None, |s, json| {
+                NpaSummary::merge(s, &npa(json), Weight::File)
+            })
+            .unwrap();
+        assert_eq!(summary.total, 4.0);
+        assert_eq!(summary.classes_average, Some(6.0));
+    }
+
+    #[test]
+    fn broken_json_reports_its_own_error_rather_than_ndjson_lines() {
+        let pretty = serde_json::to_string_pretty(&unit("src/a.rs", 10.0, 3.0)).unwrap();
+        let broken = pretty.replacen(':', "", 1);
+        let err = parse_metrics("a.json", &broken, true)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Failed to parse a.json:"), "{}", err);
+        assert!(!err.contains("line 1:"), "{}", err);
+
+        let lines = [unit("src/a.rs", 10.0, 3.0), unit("src/b.rs", 5.0, 1.0)]
+            .map(|value| value.to_string())
+            .join("\n");
+        assert_eq!(parse_metrics("batch.json", &lines, true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn repeated_records_count_as_one_file() {
+        let lines = [
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/b.rs", 5.0, 1.0),
         ]
+        .map(|value| value.to_string())
+        .join("\n");
+        let data = parse_metrics("batch.ndjson", &lines, true).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(
+            MetricsSummary::summarize(&data, &SummaryOptions::default()).files(),
+            2
+        );
+    }
+
+    #[test]
+    fn file_summaries_are_one_json_object_per_line() {
+        let data = parse(vec![
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/b.rs", 20.0, 4.0),
+        ]);
+        let mut out = Vec::new();
+        write_file_summaries(&mut out, &data, &SummaryOptions::default()).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["file"], "src/b.rs");
+        assert_eq!(lines[1]["summary"]["loc"]["sloc"], 20.0);
+    }
+
+    #[test]
+    fn precision_can_be_set_per_metric() {
+        let data = parse(vec![unit("src/a.rs", 10.0, 3.0)]);
+        let summary = MetricsSummary::summarize(&data, &SummaryOptions::default());
+        let fmt = NumberFormat {
+            precision: BTreeMap::from([
+                ("loc".to_owned(), 0),
+                ("loc.sloc".to_owned(), 1),
+                ("cyclomatic.average".to_owned(), 1),
+            ]),
+            ..NumberFormat::default()
+        };
+        let sections = summary.sections(&fmt);
+        let detail = |title: &str, key: &str| {
+            let section = sections.iter().find(|s| s.title == title).unwrap();
+            let details = section.details.as_ref().unwrap();
+            details.iter().find(|(k, _)| k == key).unwrap().1.clone()
+        };
+        assert_eq!(detail("Lines of Code", "SLOC"), "10.0");
+        assert_eq!(detail("Lines of Code", "PLOC"), "10");
+        assert_eq!(detail("Cyclomatic Complexity", "Sum"), "3.00");
+        assert_eq!(detail("Cyclomatic Complexity", "Average"), "3.0");
+    }
+
+    #[test]
+    fn chosen_mi_variant_is_the_headline() {
+        let mut file = unit("src/a.rs", 10.0, 3.0);
+        file["metrics"]["mi"] =
+            serde_json::json!({ "mi_original": 120.0, "mi_sei": 90.0, "mi_visual_studio": 70.0 });
+        let options = SummaryOptions {
+            mi_variant: MiVariant::Sei,
+            ..SummaryOptions::default()
+        };
+        let summary = MetricsSummary::summarize(&parse(vec![file]), &options);
+        let sections = summary.sections(&NumberFormat::default());
+        let mi = sections
+            .iter()
+            .find(|s| s.title == "Maintainability Index")
+            .unwrap();
+        assert_eq!(mi.key, Some("mi.mi_sei"));
+        assert_eq!(mi.value, Some(90.0));
+        assert_eq!(mi.headline().unwrap().0, "MI SEI");
+    }
+
+    #[test]
+    fn mi_settings_resolve_onto_the_headline_once() {
+        let settings = [
+            ("cyclomatic.average", 20.0),
+            ("mi.mi_original", 40.0),
+            ("mi.mi_sei", 30.0),
+        ];
+        assert_eq!(
+            MiVariant::Sei.resolve(settings),
+            [("cyclomatic.average", 20.0), ("mi.mi_sei", 30.0)]
+        );
+        assert_eq!(
+            MiVariant::VisualStudio.resolve(settings),
+            [("cyclomatic.average", 20.0), ("mi.mi_visual_studio", 40.0)]
+        );
+        assert_eq!(
+            MiVariant::Original.resolve([("loc.sloc", 1.0)]),
+            [("loc.sloc", 1.0)]
+        );
+    }
+
+    fn summary_of(values: Vec<serde_json::Value>, options: &SummaryOptions) -> MetricsSummary {
+        MetricsSummary::summarize(&parse(values), options)
+    }
+
+    #[test]
+    fn empty_input_has_no_values() {
+        let summary = MetricsSummary::summarize(&[], &SummaryOptions::default());
+        assert_eq!(summary.files(), 0);
+        assert!(!summary
+            .values()
+            .keys()
+            .any(|key| key.starts_with("cyclomatic.")));
+        assert!(summary.validate().is_empty());
+    }
+
+    #[test]
+    fn single_file_is_its_own_summary() {
+        let values = summary_of(
+            vec![unit("src/a.rs", 10.0, 3.0)],
+            &SummaryOptions::default(),
+        )
+        .values();
+        assert_eq!(values["cyclomatic.sum"], 3.0);
+        assert_eq!(values["cyclomatic.average"], 3.0);
+        assert_eq!(values["cyclomatic.min"], 1.0);
+        assert_eq!(values["cyclomatic.max"], 3.0);
+        assert_eq!(values["cyclomatic.file_min"], 3.0);
+        assert_eq!(values["cyclomatic.file_max"], 3.0);
+        assert_eq!(values["loc.sloc"], 10.0);
+    }
+
+    #[test]
+    fn multiple_files_are_aggregated() {
+        let files = vec![
+            unit("src/a.rs", 10.0, 3.0),
+            unit("src/b.rs", 30.0, 5.0),
+            unit("src/c.rs", 20.0, 4.0),
+        ];
+        let summary = summary_of(files, &SummaryOptions::default());
+        let values = summary.values();
+        assert_eq!(summary.files(), 3);
+        assert_eq!(values["cyclomatic.sum"], 12.0);
+        assert_eq!(values["cyclomatic.average"], 4.0);
+        assert_eq!(values["cyclomatic.min"], 1.0);
+        assert_eq!(values["cyclomatic.max"], 5.0);
+        assert_eq!(values["cyclomatic.file_min"], 3.0);
+        assert_eq!(values["cyclomatic.file_max"], 5.0);
+        assert_eq!(values["loc.sloc"], 60.0);
+        assert_eq!(values["loc.sloc_file_min"], 10.0);
+        assert_eq!(values["loc.sloc_file_max"], 30.0);
+    }
+
+    #[test]
+    fn file_extremes_skip_files_without_the_value() {
+        let mut no_sum = unit("src/b.rs", 5.0, 1.0);
+        no_sum["metrics"]["cyclomatic"]["sum"] = serde_json::Value::Null;
+        no_sum["metrics"]["loc"]["sloc"] = serde_json::Value::Null;
+        let files = vec![
+            unit("src/a.rs", 10.0, 3.0),
+            no_sum,
+            unit("src/c.rs", 20.0, 4.0),
+        ];
+        let values = summary_of(files, &SummaryOptions::default()).values();
+        assert_eq!(values["cyclomatic.file_min"], 3.0);
+        assert_eq!(values["cyclomatic.file_max"], 4.0);
+        assert_eq!(values["loc.sloc_file_min"], 10.0);
+        assert_eq!(values["loc.sloc_file_max"], 20.0);
+    }
+
+    #[test]
+    fn missing_minimums_serialize_as_null() {
+        let mut file = unit("src/a.rs", 10.0, 3.0);
+        file["metrics"]["cyclomatic"]["min"] = serde_json::Value::Null;
+        file["metrics"]["cyclomatic"]["max"] = serde_json::Value::Null;
+        file["metrics"]["nargs"] = serde_json::json!({ "total": 2.0, "average": 1.0 });
+        let summary = summary_of(vec![file], &SummaryOptions::default());
+        let json = serde_json::to_value(&summary).unwrap();
+        assert!(json["cyclomatic"]["min"].is_null());
+        assert!(json["cyclomatic"]["max"].is_null());
+        assert!(json["nargs"]["functions_min"].is_null());
+        assert!(json["nargs"]["closures_max"].is_null());
+        let values = summary.values();
+        assert!(!values.contains_key("cyclomatic.min"));
+        assert!(!values.contains_key("nargs.functions_min"));
+        assert_eq!(values["nargs.file_min"], 2.0);
+    }
+
+    #[test]
+    fn abc_averages_only_count_files_reporting_them() {
+        let abc = |average: serde_json::Value| {
+            serde_json::json!({
+                "assignments": 4.0, "branches": 2.0, "conditions": 1.0, "magnitude": 4.6,
+                "assignments_average": average, "branches_average": 2.0,
+                "conditions_average": 1.0
+            })
+        };
+        let mut a = unit("src/a.rs", 10.0, 3.0);
+        a["metrics"]["abc"] = abc(serde_json::json!(4.0));
+        let mut b = unit("src/b.rs", 10.0, 3.0);
+        b["metrics"]["abc"] = abc(serde_json::Value::Null);
+        let values = summary_of(vec![a, b], &SummaryOptions::default()).values();
+        assert_eq!(values["abc.assignments_average"], 4.0);
+        assert_eq!(values["abc.branches_average"], 2.0);
+        assert_eq!(values["abc.assignments"], 8.0);
+    }
+
+    #[test]
+    fn abc_magnitude_is_the_headline() {
+        let mut file = unit("src/a.rs", 10.0, 3.0);
+        file["metrics"]["abc"] = serde_json::json!({
+            "assignments": 4.0, "branches": 2.0, "conditions": 1.0, "magnitude": 4.6
+        });
+        let summary = summary_of(vec![file.clone()], &SummaryOptions::default());
+        let sections = summary.sections(&NumberFormat::default());
+        let abc = sections
+            .iter()
+            .find(|s| s.title == "ABC Complexity")
+            .unwrap();
+        assert_eq!(abc.key, Some("abc.magnitude"));
+        assert_eq!(abc.value, Some(4.6));
+        assert_eq!(abc.headline().unwrap().0, "Magnitude");
+        let values = FileValues::new(&parse(vec![file])[0], &SummaryOptions::default());
+        assert_eq!(values.get("abc.magnitude"), Some(4.6));
+    }
+
+    #[test]
+    fn weighted_averages_follow_sloc() {
+        let options = SummaryOptions {
+            weighted: true,
+            ..SummaryOptions::default()
+        };
+        let files = vec![unit("src/a.rs", 10.0, 3.0), unit("src/b.rs", 30.0, 5.0)];
+        let values = summary_of(files, &options).values();
+        assert_eq!(values["cyclomatic.average"], 4.5);
+        assert_eq!(values["cyclomatic.sum"], 8.0);
+    }
+
+    #[test]
+    fn files_without_sloc_still_count_in_averages() {
+        let mut no_loc = unit("src/b.rs", 1.0, 5.0);
+        no_loc["metrics"]["loc"] = serde_json::Value::Null;
+        let files = vec![unit("src/a.rs", 10.0, 3.0), no_loc];
+
+        let values = summary_of(files.clone(), &SummaryOptions::default()).values();
+        assert_eq!(values["cyclomatic.average"], 4.0);
+        assert_eq!(values["cyclomatic.count"], 2.0);
+
+        let weighted = SummaryOptions {
+            weighted: true,
+            ..SummaryOptions::default()
+        };
+        let values = summary_of(files, &weighted).values();
+        assert!((values["cyclomatic.average"] - 35.0 / 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn custom_metrics_read_untyped_fields() {
+        let mut a = unit("src/a.rs", 10.0, 3.0);
+        a["metrics"]["patched"] = serde_json::json!({ "score": 4.0 });
+        let mut b = unit("src/b.rs", 10.0, 3.0);
+        b["metrics"]["patched"] = serde_json::json!({ "score": 8.0 });
+        let mut c = unit("src/c.rs", 10.0, 3.0);
+        c["metrics"]["patched"] = serde_json::json!({ "score": "n/a" });
+        let metric = |aggregate| CustomMetric {
+            label: "Score".to_string(),
+            pointer: "/patched/score".to_string(),
+            aggregate,
+        };
+        let options = SummaryOptions {
+            custom: vec![metric(Aggregate::Sum), metric(Aggregate::Average)],
+            ..SummaryOptions::default()
+        };
+        let summary = summary_of(vec![a, b, c.clone()], &options);
+        let headlines: Vec<_> = summary.custom.iter().map(|c| c.headline()).collect();
+        assert_eq!(headlines, [Some(12.0), Some(6.0)]);
+        assert_eq!(summary.custom[0].tally.count, 2);
+
+        let missing = summary_of(vec![c], &options);
+        assert_eq!(missing.custom[0].headline(), None);
+    }
+
+    #[test]
+    fn files_without_metrics_are_counted_but_not_summed() {
+        let mut empty = unit("src/empty.rs", 1.0, 1.0);
+        empty["metrics"] = serde_json::json!({
+            "nargs": null, "nexits": null, "cognitive": null, "cyclomatic": null,
+            "halstead": null, "loc": null, "nom": null, "mi": null, "abc": null,
+            "wmc": null, "npm": null, "npa": null
+        });
+        let summary = summary_of(vec![empty.clone()], &SummaryOptions::default());
+        assert_eq!(summary.files(), 1);
+        assert!(!summary.values().contains_key("cyclomatic.sum"));
+
+        let summary = summary_of(
+            vec![empty, unit("src/a.rs", 10.0, 3.0)],
+            &SummaryOptions::default(),
+        );
+        let values = summary.values();
+        assert_eq!(summary.files(), 2);
+        assert_eq!(values["cyclomatic.sum"], 3.0);
+        assert_eq!(values["cyclomatic.average"], 3.0);
+        assert_eq!(values["cyclomatic.file_min"], 3.0);
     }
 }
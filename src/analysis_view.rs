@@ -0,0 +1,383 @@
+use ratatui::widgets::Table;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    analysis::{
+        create_compact_table, create_summary_table, sections_to_markdown, FileValues,
+        MetricsSummary, Section, SummaryGroup, SummaryOptions,
+    },
+    budget::{budget_section, BudgetCheck, Budgets},
+    format::{NumberFormat, ValueMode},
+    history::HistoryEntry,
+    hotspots::{collect_files, collect_functions, FunctionRecord},
+    metrics::JsonData,
+    thresholds::{Level, Thresholds},
+};
+
+pub const ALL_GROUP: &str = "All";
+
+pub struct AnalysisView {
+    pub root: PathBuf,
+    /// The overall summary, then the tabs (per directory given, per language when there are
+    /// several), then one group per subdirectory of the analyzed tree.
+    pub groups: Vec<SummaryGroup>,
+    /// How many of `groups` are shown as tabs; the rest are subdirectories.
+    pub tabs: usize,
+    pub group: usize,
+    pub format: NumberFormat,
+    pub sections: Vec<Section>,
+    pub expanded: Vec<bool>,
+    pub selected: usize,
+    pub ignored: usize,
+    pub functions: Vec<FunctionRecord>,
+    /// File-level values, for the per-file chart.
+    pub files: Vec<FunctionRecord>,
+    pub scroll: usize,
+    pub compact: bool,
+    /// Show totals divided by the number of functions instead of raw sums.
+    pub per_function: bool,
+    /// Project budgets, listed first in the summary when any are configured.
+    pub budgets: Budgets,
+    pub elapsed: Duration,
+}
+
+impl AnalysisView {
+    pub fn from_data(
+        root: &Path,
+        data: &[JsonData],
+        options: &SummaryOptions,
+        format: NumberFormat,
+    ) -> Self {
+        let all: Vec<&JsonData> = data.iter().collect();
+        let mut groups = vec![SummaryGroup::new(ALL_GROUP, &all, options)];
+        let languages = SummaryGroup::by_language(data, options);
+        // A single language would only repeat the overall summary.
+        if languages.len() > 1 {
+            groups.extend(languages);
+        }
+        let tabs = groups.len();
+        groups.extend(SummaryGroup::by_directory(data, options));
+        let sections = groups[0].summary.sections(&format);
+        let expanded = vec![false; sections.len()];
+        Self {
+            root: root.to_path_buf(),
+            groups,
+            tabs,
+            group: 0,
+            format,
+            sections,
+            expanded,
+            selected: 0,
+            ignored: 0,
+            functions: collect_functions(data),
+            files: collect_files(data),
+            scroll: 0,
+            compact: false,
+            per_function: false,
+            budgets: Budgets::default(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Aggregates several directories into one view, with a tab for each directory after
+    /// the overall summary.
+    pub fn from_roots(
+        root: &Path,
+        roots: Vec<(String, Vec<JsonData>)>,
+        options: &SummaryOptions,
+        format: NumberFormat,
+    ) -> Self {
+        let directories: Vec<_> = roots
+            .iter()
+            .map(|(name, data)| {
+                SummaryGroup::new(name.clone(), &data.iter().collect::<Vec<_>>(), options)
+            })
+            .collect();
+        let data: Vec<JsonData> = roots.into_iter().flat_map(|(_, data)| data).collect();
+        let mut view = Self::from_data(root, &data, options, format);
+        view.tabs += directories.len();
+        view.groups.splice(1..1, directories);
+        view
+    }
+
+    /// True when the analysis found no metrics at all, so there is nothing to summarize.
+    pub fn is_empty(&self) -> bool {
+        self.groups[0].summary.files() == 0
+    }
+
+    pub fn empty_message(&self) -> String {
+        format!("No JSON metrics files found under {}", self.root.display())
+    }
+
+    pub fn summary(&self) -> &MetricsSummary {
+        &self.groups[self.group].summary
+    }
+
+    pub fn contributions(&self) -> &[FileValues] {
+        &self.groups[self.group].files
+    }
+
+    pub fn selected_section(&self) -> Option<&Section> {
+        self.sections.get(self.selected)
+    }
+
+    /// The selected row as shown while collapsed, e.g. `Cyclomatic Complexity Sum: 120`.
+    pub fn selected_line(&self) -> Option<String> {
+        let section = self.selected_section()?;
+        Some(match section.headline() {
+            Some((key, value)) => format!("{} {}: {}", section.title, key, value),
+            None => format!("{}: N/A", section.title),
+        })
+    }
+
+    pub fn group_name(&self) -> &str {
+        &self.groups[self.group].name
+    }
+
+    pub fn has_groups(&self) -> bool {
+        self.tabs > 1
+    }
+
+    /// Cycles through the tabs; from a subdirectory, moves to the first or last tab.
+    pub fn next_group(&mut self) {
+        let next = if self.group + 1 < self.tabs {
+            self.group + 1
+        } else {
+            0
+        };
+        self.select_group(next);
+    }
+
+    pub fn previous_group(&mut self) {
+        let previous = match self.group {
+            0 => self.tabs - 1,
+            group => (group - 1).min(self.tabs - 1),
+        };
+        self.select_group(previous);
+    }
+
+    pub fn subdirectories(&self) -> &[SummaryGroup] {
+        &self.groups[self.tabs..]
+    }
+
+    /// Shows the summary of the `index`-th subdirectory.
+    pub fn select_subdirectory(&mut self, index: usize) {
+        if self.tabs + index < self.groups.len() {
+            self.select_group(self.tabs + index);
+        }
+    }
+
+    fn select_group(&mut self, group: usize) {
+        self.group = group;
+        self.refresh_sections();
+    }
+
+    pub fn toggle_per_function(&mut self) {
+        self.per_function = !self.per_function;
+        self.refresh_sections();
+    }
+
+    pub fn toggle_percentages(&mut self) {
+        self.format.percentages = !self.format.percentages;
+        self.refresh_sections();
+    }
+
+    /// Switches every block between both its totals and averages, only totals, and only
+    /// averages.
+    pub fn cycle_values(&mut self) -> ValueMode {
+        self.format.values = self.format.values.next();
+        self.refresh_sections();
+        self.format.values
+    }
+
+    /// Back to the overall summary, collapsed and scrolled to the top, with the display toggles
+    /// off.
+    pub fn reset(&mut self) {
+        self.group = 0;
+        self.selected = 0;
+        self.scroll = 0;
+        self.compact = false;
+        self.per_function = false;
+        self.format.percentages = false;
+        self.format.values = ValueMode::default();
+        self.expanded.clear();
+        self.refresh_sections();
+    }
+
+    pub fn set_budgets(&mut self, budgets: Budgets) {
+        self.budgets = budgets;
+        self.refresh_sections();
+    }
+
+    /// Each budget against the raw summary of the current tab, even in per-function mode.
+    pub fn budget_checks(&self) -> Vec<BudgetCheck> {
+        let summary = self.summary();
+        self.budgets.check(&summary.values(), summary.mi_variant())
+    }
+
+    fn refresh_sections(&mut self) {
+        let sections = if self.per_function {
+            self.summary().per_function().sections(&self.format)
+        } else {
+            self.summary().sections(&self.format)
+        };
+        self.sections = budget_section(&self.budget_checks(), &self.format)
+            .into_iter()
+            .chain(sections)
+            .collect();
+        self.expanded.resize(self.sections.len(), false);
+        self.selected = self.selected.min(self.sections.len().saturating_sub(1));
+    }
+
+    pub fn next(&mut self) {
+        if self.selected < self.sections.len().saturating_sub(1) {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+    }
+
+    /// Moves the selection to the next (or previous) section whose headline crosses a
+    /// threshold, wrapping around. Returns false when no section is flagged.
+    pub fn jump_flagged(&mut self, thresholds: &Thresholds, forward: bool) -> bool {
+        let len = self.sections.len();
+        let flagged = (1..=len)
+            .map(|step| {
+                if forward {
+                    (self.selected + step) % len
+                } else {
+                    (self.selected + len - step) % len
+                }
+            })
+            .find(|&index| self.sections[index].level(thresholds) != Level::Ok);
+        if let Some(index) = flagged {
+            self.selected = index;
+        }
+        flagged.is_some()
+    }
+
+    pub fn toggle(&mut self) {
+        if self.compact {
+            return;
+        }
+        if let Some(expanded) = self.expanded.get_mut(self.selected) {
+            *expanded = !*expanded;
+        }
+    }
+
+    fn widest_value(&self) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|section| section.details.iter().flatten())
+            .map(|(key, value)| key.chars().count() + 2 + value.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn scroll_right(&mut self) {
+        if self.scroll + 1 < self.widest_value() {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn scroll_left(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn table(
+        &self,
+        focused: bool,
+        thresholds: &Thresholds,
+        history: &[HistoryEntry],
+    ) -> (Table<'static>, Option<usize>) {
+        if self.compact {
+            let table = create_compact_table(&self.sections, &self.format, self.scroll, thresholds);
+            return (table, focused.then_some(self.selected));
+        }
+        create_summary_table(
+            &self.sections,
+            &self.expanded,
+            focused.then_some(self.selected),
+            self.scroll,
+            thresholds,
+            self.trend_history(history),
+        )
+    }
+
+    /// Snapshots record the overall raw summary, so trends are only drawn next to it.
+    fn trend_history<'a>(&self, history: &'a [HistoryEntry]) -> &'a [HistoryEntry] {
+        if self.group == 0 && !self.per_function {
+            history
+        } else {
+            &[]
+        }
+    }
+
+    pub fn title(&self) -> String {
+        if self.group == 0 {
+            "Metrics Summary".to_owned()
+        } else {
+            format!("Metrics Summary ({})", self.group_name())
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = sections_to_markdown(&self.title(), &self.sections);
+        markdown.push_str(&format!("\n{}\n", self.overview()));
+        markdown
+    }
+
+    pub fn overview(&self) -> String {
+        let overview = self.summary().overview(&self.format);
+        if self.per_function {
+            format!("{}  (per function)", overview)
+        } else {
+            overview
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(names: &[&str]) -> AnalysisView {
+        let data: Vec<JsonData> = names
+            .iter()
+            .map(|name| {
+                serde_json::from_value(serde_json::json!({
+                    "name": name, "start_line": 1, "end_line": 1, "kind": "unit",
+                    "spaces": [], "metrics": null
+                }))
+                .unwrap()
+            })
+            .collect();
+        let options = SummaryOptions::default();
+        AnalysisView::from_data(Path::new("."), &data, &options, NumberFormat::default())
+    }
+
+    #[test]
+    fn a_single_language_gets_no_tab() {
+        let view = view(&["src/a.rs", "src/b.rs"]);
+        assert_eq!(view.tabs, 1);
+        assert!(!view.has_groups());
+    }
+
+    #[test]
+    fn several_languages_get_a_tab_each() {
+        let view = view(&["src/a.rs", "src/b.py"]);
+        let tabs: Vec<&str> = view.groups[..view.tabs]
+            .iter()
+            .map(|group| group.name.as_str())
+            .collect();
+        assert_eq!(tabs, [ALL_GROUP, "Python", "Rust"]);
+        assert!(view.has_groups());
+    }
+}
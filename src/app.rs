@@ -0,0 +1,539 @@
+use ratatui::widgets::Table;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    analysis::{create_directories_table, MetricsSummary, Section, SummaryOptions},
+    analysis_view::AnalysisView,
+    bookmarks::Bookmarks,
+    budget::{verdict, BudgetCheck},
+    clipboard::Clipboard,
+    config::Config,
+    error::{AppError, AppResult},
+    history::History,
+    hotspots::{top_functions, Hotspot, HotspotMetric, DEFAULT_TOP},
+    job::AnalysisJob,
+    logger,
+    navigator::FileNavigator,
+    recents::Recents,
+    space_tree::SpaceTree,
+    thresholds::ThresholdField,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Focus {
+    #[default]
+    Files,
+    Analysis,
+    Details,
+}
+
+impl Focus {
+    /// The default key that focuses this pane, also shown in its border title.
+    pub fn number(self) -> char {
+        match self {
+            Focus::Analysis => '1',
+            Focus::Files => '2',
+            Focus::Details => '3',
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Focus::Files => Focus::Analysis,
+            Focus::Analysis => Focus::Details,
+            Focus::Details => Focus::Files,
+        }
+    }
+}
+
+pub enum Popup {
+    Bookmarks {
+        selected: usize,
+    },
+    Recents {
+        selected: usize,
+    },
+    History,
+    Hotspots {
+        metric: HotspotMetric,
+        /// The top functions by `metric`, recomputed by `refresh_hotspots` rather than on
+        /// every frame.
+        hotspots: Vec<Hotspot>,
+        selected: usize,
+        scroll: usize,
+    },
+    /// Bar chart of the files with the highest value of `metric`.
+    Chart {
+        metric: HotspotMetric,
+        /// Every file ranked by `metric`, worst first, of which the chart draws as many as fit.
+        files: Vec<Hotspot>,
+    },
+    Thresholds {
+        selected: usize,
+        field: ThresholdField,
+    },
+}
+
+/// A piece of transient UI state that `Esc` dismisses, innermost last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Details,
+    Filter,
+    Job,
+    Search,
+    Prompt,
+    Popup,
+}
+
+/// A table shown in the details pane, with a row cursor while the pane has focus.
+pub struct DetailsTable {
+    pub title: String,
+    pub table: Table<'static>,
+    /// Each row as plain `key: value` text, for copying it.
+    pub lines: Vec<String>,
+    /// Rows are the analysis' subdirectories, and `Enter` shows the selected one's summary.
+    pub subdirectories: bool,
+}
+
+impl DetailsTable {
+    pub fn new(title: impl Into<String>, table: Table<'static>, lines: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            table,
+            lines,
+            subdirectories: false,
+        }
+    }
+
+    pub fn line(&self, row: usize) -> Option<&str> {
+        self.lines.get(row).map(String::as_str)
+    }
+}
+
+const DEFAULT_FILES_PERCENT: u16 = 70;
+const FILES_PERCENT_STEP: u16 = 5;
+
+/// Layout and display settings that `0` restores, leaving the directory and analysis alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewState {
+    pub focus: Focus,
+    pub show_log: bool,
+    pub presentation: bool,
+    /// Share of the right column given to the Files pane, in percent.
+    pub files_percent: u16,
+    /// Whether keys are typed into the file filter.
+    pub searching: bool,
+    /// Fuzzy filter of the Files pane, which the navigator applies to its entries.
+    pub filter: String,
+    /// Row cursor of the table in the Details pane.
+    pub details_row: usize,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            focus: Focus::default(),
+            show_log: false,
+            presentation: false,
+            files_percent: DEFAULT_FILES_PERCENT,
+            searching: false,
+            filter: String::new(),
+            details_row: 0,
+        }
+    }
+}
+
+impl ViewState {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Gives the Details pane more (or less) of the right column.
+    pub fn resize_details(&mut self, grow: bool) {
+        self.files_percent = if grow {
+            self.files_percent
+                .saturating_sub(FILES_PERCENT_STEP)
+                .max(FILES_PERCENT_STEP)
+        } else {
+            (self.files_percent + FILES_PERCENT_STEP).min(100 - FILES_PERCENT_STEP)
+        };
+    }
+
+    /// Moves the Details pane's row cursor down, staying within its `rows`.
+    pub fn next_details_row(&mut self, rows: usize) {
+        if self.details_row + 1 < rows {
+            self.details_row += 1;
+        }
+    }
+
+    pub fn previous_details_row(&mut self) {
+        self.details_row = self.details_row.saturating_sub(1);
+    }
+}
+
+pub struct Status {
+    pub message: String,
+    pub is_error: bool,
+}
+
+pub struct App {
+    pub config: Config,
+    pub options: SummaryOptions,
+    pub navigator: FileNavigator,
+    pub analysis: Option<AnalysisView>,
+    pub details: Option<DetailsTable>,
+    pub tree: Option<SpaceTree>,
+    pub status: Option<Status>,
+    pub view: ViewState,
+    pub bookmarks: Bookmarks,
+    pub recents: Recents,
+    pub popup: Option<Popup>,
+    pub clipboard: Clipboard,
+    pub history: History,
+    pub job: Option<AnalysisJob>,
+    /// Text typed into the go-to-path prompt while it is open.
+    pub prompt: Option<String>,
+}
+
+impl App {
+    pub fn new(path: &Path, config: Config, options: SummaryOptions) -> AppResult<Self> {
+        let mut navigator = FileNavigator::new(path)?;
+        navigator.follow_symlinks = options.follow_symlinks;
+        Ok(Self {
+            config,
+            options,
+            navigator,
+            analysis: None,
+            details: None,
+            tree: None,
+            status: None,
+            view: ViewState::default(),
+            bookmarks: Bookmarks::load(),
+            recents: Recents::load(),
+            popup: None,
+            clipboard: Clipboard::default(),
+            history: History::load(),
+            job: None,
+            prompt: None,
+        })
+    }
+
+    /// The currently active layers, from the outermost to the one `Esc` dismisses next.
+    pub fn layers(&self) -> Vec<Layer> {
+        [
+            (
+                Layer::Details,
+                self.details.is_some() || self.tree.is_some(),
+            ),
+            (Layer::Filter, !self.view.filter.is_empty()),
+            (Layer::Job, self.job.is_some()),
+            (Layer::Search, self.view.searching),
+            (Layer::Prompt, self.prompt.is_some()),
+            (Layer::Popup, self.popup.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(layer, active)| active.then_some(layer))
+        .collect()
+    }
+
+    /// Dismisses the innermost layer of UI state, if any.
+    pub fn escape(&mut self) -> AppResult<()> {
+        match self.layers().last() {
+            Some(Layer::Popup) => self.close_popup()?,
+            Some(Layer::Prompt) => self.prompt = None,
+            Some(Layer::Search) => {
+                self.view.searching = false;
+                self.set_filter(String::new());
+            }
+            Some(Layer::Job) => self.cancel_job()?,
+            Some(Layer::Filter) => self.set_filter(String::new()),
+            Some(Layer::Details) => self.clear_details(),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Moves focus to the next pane, skipping the details pane while it is empty.
+    pub fn next_focus(&mut self) {
+        self.view.focus = self.view.focus.next();
+        if self.view.focus == Focus::Details && self.tree.is_none() && self.details.is_none() {
+            self.view.focus = self.view.focus.next();
+        }
+    }
+
+    /// Focuses `focus`, unless it is the details pane and there is nothing in it.
+    pub fn focus_pane(&mut self, focus: Focus) {
+        if focus != Focus::Details || self.tree.is_some() || self.details.is_some() {
+            self.view.focus = focus;
+        }
+    }
+
+    /// Filters the Files pane by `filter`.
+    pub fn set_filter(&mut self, filter: String) {
+        self.view.filter = filter;
+        self.navigator.filter(&self.view.filter);
+    }
+
+    /// Shows a finished directory load; moving to another directory drops the file filter.
+    pub fn poll_navigator(&mut self) -> AppResult<()> {
+        if self.navigator.poll(&self.view.filter)? {
+            self.view.filter.clear();
+        }
+        Ok(())
+    }
+
+    pub fn clear_details(&mut self) {
+        self.details = None;
+        self.view.details_row = 0;
+        self.tree = None;
+        if self.view.focus == Focus::Details {
+            self.view.focus = Focus::Files;
+        }
+    }
+
+    /// Recomputes the functions or files ranked by an open hotspots or chart popup, after its
+    /// metric or the analysis changed.
+    pub fn refresh_hotspots(&mut self) {
+        let Some(view) = self.analysis.as_ref() else {
+            return;
+        };
+        match self.popup.as_mut() {
+            Some(Popup::Hotspots {
+                metric,
+                hotspots,
+                selected,
+                ..
+            }) => {
+                *hotspots = top_functions(&view.functions, *metric, DEFAULT_TOP);
+                *selected = (*selected).min(hotspots.len().saturating_sub(1));
+            }
+            Some(Popup::Chart { metric, files }) => {
+                *files = top_functions(&view.files, *metric, view.files.len());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn close_popup(&mut self) -> AppResult<()> {
+        if let Some(Popup::Thresholds { .. }) = self.popup.take() {
+            self.config.save()?;
+            self.info("Saved thresholds");
+        }
+        Ok(())
+    }
+
+    /// Returns scrolling, focus, the file filter, pane sizes and the summary's display toggles
+    /// to their defaults, keeping the current directory and analysis.
+    pub fn reset_view(&mut self) {
+        self.view.reset();
+        self.navigator.filter(&self.view.filter);
+        if let Some(view) = self.analysis.as_mut() {
+            view.reset();
+        }
+        self.info("View reset");
+    }
+
+    /// Switches whether navigation and later analyses follow symlinks; a finished analysis is
+    /// left as it was until the directory is analyzed again.
+    pub fn toggle_follow_symlinks(&mut self) {
+        self.options.follow_symlinks = !self.options.follow_symlinks;
+        self.navigator.follow_symlinks = self.options.follow_symlinks;
+        let mut message = if self.options.follow_symlinks {
+            "Following symlinks".to_owned()
+        } else {
+            "Not following symlinks".to_owned()
+        };
+        if self.analysis.is_some() {
+            message.push_str(" (analyze again to apply)");
+        }
+        self.info(message);
+    }
+
+    /// Hides the file list so the summary fills the screen; only the Analysis pane is focusable.
+    pub fn toggle_presentation(&mut self) {
+        self.view.presentation = !self.view.presentation;
+        if self.view.presentation {
+            self.view.focus = Focus::Analysis;
+        }
+    }
+
+    /// Starts analyzing directories or archives in the background, unless an analysis is
+    /// already running, in which case the request is ignored with a note in the status bar.
+    /// Several paths are aggregated into one summary with a tab per path.
+    pub fn start_analysis(&mut self, paths: &[PathBuf]) -> bool {
+        if let Some(job) = &self.job {
+            let message = format!(
+                "Still analyzing {}, press Esc to cancel it first",
+                job.path.display()
+            );
+            self.info(message);
+            return false;
+        }
+        self.clear_details();
+        let target = match paths {
+            [path] => path.display().to_string(),
+            _ => format!("{} directories", paths.len()),
+        };
+        self.info(format!("Analyzing {}… (Esc to cancel)", target));
+        self.job = Some(AnalysisJob::spawn(
+            paths,
+            self.options.clone(),
+            self.config.number_format.clone(),
+        ));
+        true
+    }
+
+    /// Starts analyzing exactly `files` in the background, as listed by `--files-from`.
+    pub fn start_file_list_analysis(&mut self, root: &Path, files: Vec<PathBuf>) {
+        self.info(format!(
+            "Analyzing {} listed files… (Esc to cancel)",
+            files.len()
+        ));
+        self.job = Some(AnalysisJob::spawn_files(
+            root,
+            files,
+            self.options.clone(),
+            self.config.number_format.clone(),
+        ));
+    }
+
+    pub fn poll_job(&mut self) {
+        let Some(result) = self.job.as_ref().and_then(AnalysisJob::poll) else {
+            return;
+        };
+        self.job = None;
+        match result {
+            Ok(mut view) => {
+                if view.is_empty() {
+                    self.error(view.empty_message());
+                    self.analysis = Some(view);
+                    self.refresh_hotspots();
+                    return;
+                }
+                for problem in view.summary().validate() {
+                    logger::warn(format!("{}: {}", view.root.display(), problem));
+                }
+                let mut message = format!(
+                    "Analyzed {} files in {} in {:.1}s",
+                    view.groups[0].summary.files(),
+                    view.root.display(),
+                    view.elapsed.as_secs_f64()
+                );
+                if view.ignored > 0 {
+                    message.push_str(&format!(
+                        " ({} files skipped by ignore rules)",
+                        view.ignored
+                    ));
+                }
+                view.set_budgets(self.config.budgets.clone());
+                let checks = view.budget_checks();
+                if checks.is_empty() {
+                    self.info(message);
+                } else if checks.iter().any(BudgetCheck::is_over) {
+                    self.error(format!("{} — {}", message, verdict(&checks)));
+                } else {
+                    self.info(format!("{} — {}", message, verdict(&checks)));
+                }
+                self.analysis = Some(view);
+                self.refresh_hotspots();
+            }
+            Err(AppError::Cancelled) => {}
+            Err(err) => self.error(err.to_string()),
+        }
+    }
+
+    pub fn cancel_job(&mut self) -> AppResult<()> {
+        let Some(job) = self.job.take() else {
+            return Ok(());
+        };
+        job.cancel();
+        self.info(format!("Cancelled analysis of {}", job.path.display()));
+        // Entering the analyzed directory may still be loading, so go by where the navigator
+        // is headed rather than what it shows.
+        if job.roots.len() == 1 && self.navigator.destination() == job.path {
+            self.navigator.reveal(&job.path);
+        }
+        Ok(())
+    }
+
+    pub fn open_dir(&mut self, path: &Path) -> AppResult<()> {
+        if !path.is_dir() {
+            return Err(AppError::DirReadError(path.display().to_string()));
+        }
+        let show_hidden = self.navigator.show_hidden;
+        self.navigator = FileNavigator::new(path)?;
+        self.navigator.show_hidden = show_hidden;
+        self.view.filter.clear();
+        self.navigator.follow_symlinks = self.options.follow_symlinks;
+        Ok(())
+    }
+
+    /// Shows the analysis' subdirectories in the details pane, with the one shown in the
+    /// summary open and selected.
+    pub fn show_directories(&mut self) {
+        self.details = self.directories_table();
+        self.view.details_row = self
+            .analysis
+            .as_ref()
+            .and_then(|view| view.group.checked_sub(view.tabs))
+            .unwrap_or(0);
+    }
+
+    /// The analysis' subdirectories as a details table, with the one shown in the summary open.
+    fn directories_table(&self) -> Option<DetailsTable> {
+        let view = self.analysis.as_ref()?;
+        let shown = view.group.checked_sub(view.tabs);
+        let (table, lines) =
+            create_directories_table(view.subdirectories(), shown, &self.config.number_format);
+        let mut details = DetailsTable::new(
+            format!("Directories of {}", view.root.display()),
+            table,
+            lines,
+        );
+        details.subdirectories = true;
+        Some(details)
+    }
+
+    /// Shows the summary of the subdirectory selected in the details pane.
+    pub fn show_subdirectory(&mut self) {
+        if !self
+            .details
+            .as_ref()
+            .is_some_and(|details| details.subdirectories)
+        {
+            return;
+        }
+        if let Some(view) = self.analysis.as_mut() {
+            view.select_subdirectory(self.view.details_row);
+        }
+        self.show_directories();
+    }
+
+    /// Sections that can carry a threshold, with values from the current analysis if any.
+    pub fn threshold_sections(&self) -> Vec<Section> {
+        let sections = match &self.analysis {
+            Some(view) => view.summary().sections(&self.config.number_format),
+            None => MetricsSummary::default().sections(&self.config.number_format),
+        };
+        sections
+            .into_iter()
+            .filter(|section| section.key.is_some())
+            .collect()
+    }
+
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.status = Some(Status {
+            message: message.into(),
+            is_error: false,
+        });
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        logger::warn(message.clone());
+        self.status = Some(Status {
+            message,
+            is_error: true,
+        });
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{config_dir, load_json},
+    error::{AppError, AppResult},
+};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    pub paths: Vec<PathBuf>,
+}
+
+impl Bookmarks {
+    fn file() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(BOOKMARKS_FILE))
+    }
+
+    pub fn load() -> Self {
+        load_json(Self::file(), "bookmarks")
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let path = Self::file()
+            .ok_or_else(|| AppError::ConfigError("no config directory available".into()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::ConfigError(format!("failed to encode bookmarks: {}", e)))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn toggle(&mut self, path: &Path) -> bool {
+        if let Some(index) = self.paths.iter().position(|p| p == path) {
+            self.paths.remove(index);
+            false
+        } else {
+            self.paths.push(path.to_path_buf());
+            true
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.paths.len() {
+            self.paths.remove(index);
+        }
+    }
+}
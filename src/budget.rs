@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    analysis::{MiVariant, Section},
+    format::NumberFormat,
+    history::higher_is_worse,
+    thresholds::Level,
+};
+
+/// Target lines keyed like `--fail-if` metrics, e.g. `loc.sloc_file_max`. Unlike thresholds a
+/// budget has no severity bands: a metric is either within it or over it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Budgets {
+    limits: BTreeMap<String, f64>,
+}
+
+pub struct BudgetCheck {
+    pub key: String,
+    pub value: Option<f64>,
+    pub budget: f64,
+}
+
+impl BudgetCheck {
+    /// Past the line, which is below it for the Maintainability Index. A metric missing from
+    /// the summary can't be over.
+    pub fn is_over(&self) -> bool {
+        self.value.is_some_and(|value| {
+            if higher_is_worse(&self.key) {
+                value > self.budget
+            } else {
+                value < self.budget
+            }
+        })
+    }
+}
+
+impl Budgets {
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Checks each budget against `values`, with an MI budget judging the `mi_variant`
+    /// headline.
+    pub fn check(&self, values: &BTreeMap<String, f64>, mi_variant: MiVariant) -> Vec<BudgetCheck> {
+        mi_variant
+            .resolve(
+                self.limits
+                    .iter()
+                    .map(|(key, budget)| (key.as_str(), *budget)),
+            )
+            .into_iter()
+            .map(|(key, budget)| BudgetCheck {
+                key: key.to_owned(),
+                value: values.get(key).copied(),
+                budget,
+            })
+            .collect()
+    }
+}
+
+/// `Within budget (5/5)` or `Over budget (2 of 5)`, for the status bar.
+pub fn verdict(checks: &[BudgetCheck]) -> String {
+    let over = checks.iter().filter(|check| check.is_over()).count();
+    if over == 0 {
+        format!("Within budget ({}/{})", checks.len(), checks.len())
+    } else {
+        format!("Over budget ({} of {})", over, checks.len())
+    }
+}
+
+/// A summary section listing each budget as `value / budget (OK|OVER)` under the verdict, or
+/// `None` when no budgets are configured.
+pub fn budget_section(checks: &[BudgetCheck], fmt: &NumberFormat) -> Option<Section> {
+    if checks.is_empty() {
+        return None;
+    }
+    let over = checks.iter().filter(|check| check.is_over()).count();
+    let level = |over: bool| if over { Level::Error } else { Level::Ok };
+    let mut details = vec![("Verdict".to_owned(), verdict(checks))];
+    let mut levels = vec![level(over > 0)];
+    for check in checks {
+        let status = match (check.value, check.is_over()) {
+            (None, _) => "N/A",
+            (Some(_), true) => "OVER",
+            (Some(_), false) => "OK",
+        };
+        details.push((
+            check.key.clone(),
+            format!(
+                "{} / {} ({})",
+                fmt.metric(Some(&check.key), check.value),
+                fmt.metric(Some(&check.key), Some(check.budget)),
+                status
+            ),
+        ));
+        levels.push(level(check.is_over()));
+    }
+    Some(Section {
+        title: "Budgets".to_owned(),
+        key: None,
+        details: Some(details),
+        value: Some(over as f64),
+        levels: Some(levels),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budgets(limits: &[(&str, f64)]) -> Budgets {
+        Budgets {
+            limits: limits
+                .iter()
+                .map(|&(key, limit)| (key.to_owned(), limit))
+                .collect(),
+        }
+    }
+
+    fn values(values: &[(&str, f64)]) -> BTreeMap<String, f64> {
+        values
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value))
+            .collect()
+    }
+
+    #[test]
+    fn maintainability_is_over_budget_below_the_line() {
+        let budgets = budgets(&[("mi.mi_original", 65.0)]);
+        assert!(
+            budgets.check(&values(&[("mi.mi_original", 60.0)]), MiVariant::Original)[0].is_over()
+        );
+        assert!(
+            !budgets.check(&values(&[("mi.mi_original", 65.0)]), MiVariant::Original)[0].is_over()
+        );
+        assert!(
+            !budgets.check(&values(&[("mi.mi_original", 80.0)]), MiVariant::Original)[0].is_over()
+        );
+    }
+
+    #[test]
+    fn other_metrics_are_over_budget_above_the_line() {
+        let budgets = budgets(&[("loc.sloc_file_max", 400.0)]);
+        assert!(budgets.check(
+            &values(&[("loc.sloc_file_max", 401.0)]),
+            MiVariant::Original
+        )[0]
+        .is_over());
+        assert!(!budgets.check(
+            &values(&[("loc.sloc_file_max", 400.0)]),
+            MiVariant::Original
+        )[0]
+        .is_over());
+    }
+
+    #[test]
+    fn missing_metrics_are_not_over_budget() {
+        let checks = budgets(&[("cyclomatic.sum", 10.0), ("mi.mi_sei", 50.0)])
+            .check(&values(&[]), MiVariant::Original);
+        assert!(checks
+            .iter()
+            .all(|check| check.value.is_none() && !check.is_over()));
+        assert_eq!(verdict(&checks), "Within budget (2/2)");
+    }
+
+    #[test]
+    fn mi_budgets_judge_the_headline_variant_once() {
+        let budgets = budgets(&[("mi.mi_original", 65.0), ("mi.mi_sei", 50.0)]);
+        let values = values(&[("mi.mi_sei", 55.0), ("mi.mi_visual_studio", 30.0)]);
+        let checks = budgets.check(&values, MiVariant::Sei);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(
+            (checks[0].key.as_str(), checks[0].budget),
+            ("mi.mi_sei", 50.0)
+        );
+        assert!(!checks[0].is_over());
+        let checks = budgets.check(&values, MiVariant::VisualStudio);
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].key, "mi.mi_visual_studio");
+        assert!(checks[0].is_over());
+    }
+}
@@ -0,0 +1,272 @@
+use std::{env, ffi::OsString, path::PathBuf, str::FromStr};
+
+use crate::{
+    analysis::{MetricSet, MiVariant, SummaryOptions},
+    condition::Condition,
+    config::Config,
+    error::{AppError, AppResult},
+    hotspots::HotspotMetric,
+};
+
+#[derive(Debug, Default)]
+pub struct Cli {
+    pub paths: Vec<PathBuf>,
+    pub json: bool,
+    pub fail_if: Vec<Condition>,
+    pub weighted: bool,
+    pub no_ignore: bool,
+    pub top: Option<usize>,
+    pub top_by: HotspotMetric,
+    pub presentation: bool,
+    pub list_files: bool,
+    pub summary_line: bool,
+    pub strict: bool,
+    pub dedup_spaces: bool,
+    pub follow_symlinks: bool,
+    /// Summary saved from `--json` to report the changes against.
+    pub against: Option<PathBuf>,
+    pub format: OutputFormat,
+    /// File listing the metrics files to analyze instead of walking `paths`.
+    pub files_from: Option<PathBuf>,
+    /// Metric families to summarize, from `--metrics` or else `RCA_TUI_METRICS`.
+    pub metrics: Option<MetricSet>,
+    /// Maintainability Index variant used as the headline, overriding the config.
+    pub mi_variant: Option<MiVariant>,
+    /// Count the metrics files before starting the TUI, and ask before going on with more
+    /// than this many.
+    pub confirm_above: Option<usize>,
+    /// Undocumented: analyze the paths this many times and print timings, for profiling.
+    pub bench: Option<usize>,
+}
+
+/// How headless mode prints its report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    /// The `--against` comparison alone, as CSV.
+    Csv,
+    /// One summary per file, one JSON object per line.
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(AppError::InvalidArgument(format!(
+                "Unknown format '{}', expected json, csv or jsonl",
+                s
+            ))),
+        }
+    }
+}
+
+/// Environment variable selecting the summarized metrics when `--metrics` isn't given.
+pub const METRICS_ENV: &str = "RCA_TUI_METRICS";
+
+/// The value following `option` as text; only paths may be any `OsString`.
+fn text(value: Option<OsString>, option: &str, expects: &str) -> AppResult<String> {
+    let value = value
+        .ok_or_else(|| AppError::InvalidArgument(format!("{} expects {}", option, expects)))?;
+    value.into_string().map_err(|value| {
+        AppError::InvalidArgument(format!(
+            "{} expects {}, got '{}'",
+            option,
+            expects,
+            value.to_string_lossy()
+        ))
+    })
+}
+
+impl Cli {
+    /// Parses the arguments after the program name, keeping paths as given even when they
+    /// aren't valid UTF-8.
+    pub fn parse<I: IntoIterator<Item = OsString>>(args: I) -> AppResult<Self> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.to_str().filter(|arg| arg.starts_with("--")) else {
+                cli.paths.push(PathBuf::from(arg));
+                continue;
+            };
+            match flag {
+                "--json" => cli.json = true,
+                "--weighted" => cli.weighted = true,
+                "--no-ignore" => cli.no_ignore = true,
+                "--presentation" => cli.presentation = true,
+                "--list-files" => cli.list_files = true,
+                "--summary-line" => cli.summary_line = true,
+                "--strict" => cli.strict = true,
+                "--dedup-spaces" => cli.dedup_spaces = true,
+                "--follow-symlinks" => cli.follow_symlinks = true,
+                "--against" => {
+                    let baseline = args.next().ok_or_else(|| {
+                        AppError::InvalidArgument("--against expects a summary file".into())
+                    })?;
+                    cli.against = Some(PathBuf::from(baseline));
+                }
+                "--format" => {
+                    cli.format = text(args.next(), "--format", "json, csv or jsonl")?.parse()?;
+                }
+                "--files-from" => {
+                    let list = args.next().ok_or_else(|| {
+                        AppError::InvalidArgument("--files-from expects a file".into())
+                    })?;
+                    cli.files_from = Some(PathBuf::from(list));
+                }
+                "--fail-if" => {
+                    let expr = text(args.next(), "--fail-if", "a condition")?;
+                    cli.fail_if.push(expr.parse()?);
+                }
+                "--top" => {
+                    let count = text(args.next(), "--top", "a number of functions")?;
+                    cli.top = Some(count.parse().map_err(|_| {
+                        AppError::InvalidArgument(format!("Invalid --top count '{}'", count))
+                    })?);
+                }
+                "--metrics" => {
+                    cli.metrics =
+                        Some(text(args.next(), "--metrics", "a list of metrics")?.parse()?);
+                }
+                "--mi-variant" => {
+                    let variant = text(
+                        args.next(),
+                        "--mi-variant",
+                        "original, sei or visual_studio",
+                    )?;
+                    cli.mi_variant = Some(variant.parse()?);
+                }
+                "--confirm-above" => {
+                    let count = text(args.next(), "--confirm-above", "a number of files")?;
+                    cli.confirm_above = Some(count.parse().map_err(|_| {
+                        AppError::InvalidArgument(format!(
+                            "Invalid --confirm-above count '{}'",
+                            count
+                        ))
+                    })?);
+                }
+                "--bench" => {
+                    let runs = text(args.next(), "--bench", "a number of runs")?;
+                    cli.bench =
+                        Some(runs.parse().ok().filter(|&runs| runs > 0).ok_or_else(|| {
+                            AppError::InvalidArgument(format!("Invalid --bench runs '{}'", runs))
+                        })?);
+                }
+                "--top-by" => {
+                    cli.top_by = text(args.next(), "--top-by", "a metric name")?.parse()?;
+                }
+                _ => {
+                    return Err(AppError::InvalidArgument(format!(
+                        "Unknown option '{}'",
+                        flag
+                    )))
+                }
+            }
+        }
+        if cli.format == OutputFormat::Csv && cli.against.is_none() {
+            return Err(AppError::InvalidArgument(
+                "--format csv needs a baseline given with --against".into(),
+            ));
+        }
+        if cli.format == OutputFormat::Jsonl && cli.against.is_some() {
+            return Err(AppError::InvalidArgument(
+                "--format jsonl lists files and can't be combined with --against".into(),
+            ));
+        }
+        if !cli.fail_if.is_empty() && !cli.is_headless() {
+            return Err(AppError::InvalidArgument(
+                "--fail-if is only checked in headless mode, add --json".into(),
+            ));
+        }
+        if cli.metrics.is_none() {
+            if let Ok(metrics) = env::var(METRICS_ENV) {
+                cli.metrics = Some(metrics.parse().map_err(|e| match e {
+                    AppError::InvalidArgument(message) => {
+                        AppError::InvalidArgument(format!("{}: {}", METRICS_ENV, message))
+                    }
+                    e => e,
+                })?);
+            }
+        }
+        Ok(cli)
+    }
+
+    /// Whether the summary is printed instead of opening the TUI.
+    pub fn is_headless(&self) -> bool {
+        self.json || self.against.is_some() || self.format == OutputFormat::Jsonl
+    }
+
+    pub fn summary_options(&self, config: &Config) -> SummaryOptions {
+        SummaryOptions {
+            weighted: self.weighted,
+            respect_ignore: !self.no_ignore,
+            metrics: self.metrics.unwrap_or_default(),
+            strict: self.strict,
+            dedup_spaces: self.dedup_spaces,
+            follow_symlinks: self.follow_symlinks,
+            custom: config.custom_metrics.clone(),
+            mi_variant: self.mi_variant.unwrap_or(config.mi_variant),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_are_kept_as_given() {
+        use std::os::unix::ffi::OsStringExt;
+        let path = OsString::from_vec(b"metrics-\xff".to_vec());
+        let list = OsString::from_vec(b"files-\xfe.txt".to_vec());
+        let mut given = vec![path.clone(), "--files-from".into(), list.clone()];
+        given.extend(args(&["--top", "5"]));
+        let cli = Cli::parse(given).unwrap();
+        assert_eq!(cli.paths, [PathBuf::from(path)]);
+        assert_eq!(cli.files_from, Some(PathBuf::from(list)));
+        assert_eq!(cli.top, Some(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_option_values_are_rejected() {
+        use std::os::unix::ffi::OsStringExt;
+        let given = vec!["--top".into(), OsString::from_vec(b"\xff".to_vec())];
+        assert!(matches!(
+            Cli::parse(given),
+            Err(AppError::InvalidArgument(message)) if message.starts_with("--top expects")
+        ));
+    }
+
+    #[test]
+    fn bench_needs_at_least_one_run() {
+        assert_eq!(Cli::parse(args(&["--bench", "3"])).unwrap().bench, Some(3));
+        for runs in ["0", "-1", "many"] {
+            assert!(Cli::parse(args(&["--bench", runs])).is_err(), "{}", runs);
+        }
+    }
+
+    #[test]
+    fn fail_if_needs_headless_mode() {
+        let err = Cli::parse(args(&["--fail-if", "cyclomatic.sum>1"])).unwrap_err();
+        assert!(err.to_string().contains("add --json"), "{}", err);
+        let cli = Cli::parse(args(&["--json", "--fail-if", "cyclomatic.sum>1"])).unwrap();
+        assert_eq!(cli.fail_if.len(), 1);
+    }
+
+    #[test]
+    fn missing_option_values_are_reported() {
+        assert!(Cli::parse(args(&["--format"])).is_err());
+        assert!(Cli::parse(args(&["--against"])).is_err());
+    }
+}
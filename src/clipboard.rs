@@ -0,0 +1,34 @@
+use std::{env, fs, path::PathBuf};
+
+use crate::error::AppResult;
+
+pub enum CopyTarget {
+    Clipboard,
+    File(PathBuf),
+}
+
+#[derive(Default)]
+pub struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    /// Copies `text` to the system clipboard, returning false when none is available.
+    pub fn try_copy(&mut self, text: &str) -> bool {
+        if self.inner.is_none() {
+            self.inner = arboard::Clipboard::new().ok();
+        }
+        self.inner
+            .as_mut()
+            .is_some_and(|clipboard| clipboard.set_text(text).is_ok())
+    }
+
+    pub fn copy(&mut self, text: &str, fallback_name: &str) -> AppResult<CopyTarget> {
+        if self.try_copy(text) {
+            return Ok(CopyTarget::Clipboard);
+        }
+        let path = env::temp_dir().join(fallback_name);
+        fs::write(&path, text)?;
+        Ok(CopyTarget::File(path))
+    }
+}
@@ -2,33 +2,42 @@ use ratatui::{
     layout::Constraint,
     style::{Modifier, Style},
     text::Text,
-    widgets::{Block, Borders, Cell, Row, Table},
+    widgets::{Cell, Row, Table},
 };
+use std::{env, fs, path::Path, process};
 
-use crate::{analysis, error::AppResult, navigator::FileNavigator};
+use crate::{
+    analysis::{
+        create_comparison_table, create_contributions_table, is_archive, is_metrics_file,
+        read_json_file, MetricsSummary,
+    },
+    analysis_view::AnalysisView,
+    app::{App, DetailsTable, Focus},
+    clipboard::CopyTarget,
+    error::{AppError, AppResult},
+    navigator::{absolute_path, file_name},
+    space_tree::SpaceTree,
+    ui::TerminalUI,
+};
+
+/// Where `ScreenshotCommand` writes, relative to the working directory.
+const SCREENSHOT_FILE: &str = "screenshot.txt";
 
 pub trait Command {
-    fn execute(
-        &mut self,
-        navigator: &mut FileNavigator,
-        details: &mut Option<Table>,
-        analysis: &mut Option<Table>,
-    ) -> AppResult<()>;
+    fn execute(&mut self, app: &mut App) -> AppResult<()>;
 }
 
 pub struct AnalyzeCommand;
 
 impl Command for AnalyzeCommand {
-    fn execute(
-        &mut self,
-        navigator: &mut FileNavigator,
-        details: &mut Option<Table>,
-        analysis: &mut Option<Table>,
-    ) -> AppResult<()> {
-        if let Some(path) = navigator.selected() {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        if let Some(path) = app.navigator.selected().cloned() {
             if path.is_dir() {
-                *details = None;
-                *analysis = Some(analysis::analyze_directory(path)?);
+                if app.start_analysis(&[path]) {
+                    app.navigator.enter()?;
+                }
+            } else if is_archive(&path) {
+                app.start_analysis(&[path]);
             }
         }
         Ok(())
@@ -38,18 +47,23 @@ impl Command for AnalyzeCommand {
 pub struct SelectFileCommand;
 
 impl Command for SelectFileCommand {
-    fn execute(
-        &mut self,
-        navigator: &mut FileNavigator,
-        details: &mut Option<Table>,
-        _analysis: &mut Option<Table>,
-    ) -> AppResult<()> {
-        if let Some(path) = navigator.selected() {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        if let Some(path) = app.navigator.selected().cloned() {
             if !path.is_dir() {
+                app.clear_details();
+                if is_metrics_file(&path) {
+                    let data = read_json_file(&path, &app.options);
+                    if !data.is_empty() {
+                        app.tree = Some(SpaceTree::from_data(&data));
+                        app.view.focus = Focus::Details;
+                        return Ok(());
+                    }
+                }
+                let absolute = absolute_path(&path).display().to_string();
                 let table = Table::new(
                     vec![Row::new(vec![
                         Cell::from(Text::from("Path")),
-                        Cell::from(Text::from(path.display().to_string())),
+                        Cell::from(Text::from(absolute.clone())),
                     ])],
                     [Constraint::Percentage(30), Constraint::Percentage(70)],
                 )
@@ -57,15 +71,248 @@ impl Command for SelectFileCommand {
                     Row::new(vec![Cell::from(Text::from("Path"))])
                         .style(Style::default().add_modifier(Modifier::BOLD)),
                 )
-                .block(
-                    Block::default()
-                        .title("Selected Path")
-                        .borders(Borders::ALL),
-                );
+                .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+                let lines = vec![format!("Path: {}", absolute)];
+                app.details = Some(DetailsTable::new("Selected Path", table, lines));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct CompareFileCommand;
+
+impl Command for CompareFileCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let Some(path) = app
+            .navigator
+            .selected()
+            .filter(|p| is_metrics_file(p))
+            .cloned()
+        else {
+            return Ok(());
+        };
+        let Some(view) = app.analysis.as_ref() else {
+            app.info("Analyze a directory first to compare against its average");
+            return Ok(());
+        };
+        let data = read_json_file(&path, &app.options);
+        if data.is_empty() {
+            return Err(AppError::AnalysisError(format!(
+                "no metrics in {}",
+                path.display()
+            )));
+        }
+        let file = MetricsSummary::summarize(&data, &app.options);
+        let title = format!(
+            "{} vs {} ({})",
+            file_name(&path),
+            view.root.display(),
+            view.group_name()
+        );
+        let (table, lines) =
+            create_comparison_table(&file, view.summary(), &app.config.number_format);
+        app.clear_details();
+        app.details = Some(DetailsTable::new(title, table, lines));
+        Ok(())
+    }
+}
+
+/// Breaks the selected summary row down into the files contributing to it.
+pub struct ExplainMetricCommand;
+
+impl Command for ExplainMetricCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let Some(view) = app.analysis.as_ref() else {
+            app.info("Analyze a directory first to explain its summary");
+            return Ok(());
+        };
+        let Some((title, key)) = view
+            .selected_section()
+            .and_then(|section| section.key.map(|key| (section.title.clone(), key)))
+        else {
+            app.info("This row has no per-file breakdown");
+            return Ok(());
+        };
+        let title = format!("{} by file ({})", title, view.group_name());
+        let (table, lines) =
+            create_contributions_table(view.contributions(), key, &app.config.number_format);
+        app.clear_details();
+        app.details = Some(DetailsTable::new(title, table, lines));
+        app.view.focus = Focus::Details;
+        Ok(())
+    }
+}
+
+/// Lists the analyzed tree's subdirectories in the details pane, each with its own summary.
+pub struct DirectoriesCommand;
+
+impl Command for DirectoriesCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let Some(view) = app.analysis.as_ref() else {
+            app.info("Analyze a directory first to summarize its subdirectories");
+            return Ok(());
+        };
+        if view.subdirectories().is_empty() {
+            app.info("No subdirectories to summarize");
+            return Ok(());
+        }
+        app.clear_details();
+        app.show_directories();
+        app.view.focus = Focus::Details;
+        Ok(())
+    }
+}
+
+pub struct OpenInEditorCommand<'a> {
+    pub ui: &'a mut TerminalUI,
+}
+
+impl OpenInEditorCommand<'_> {
+    fn editor() -> AppResult<String> {
+        ["VISUAL", "EDITOR"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.trim().is_empty()))
+            .ok_or_else(|| AppError::EditorError("neither $VISUAL nor $EDITOR is set".into()))
+    }
+}
 
-                *details = Some(table);
+impl Command for OpenInEditorCommand<'_> {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let Some(path) = app.navigator.selected().filter(|p| p.is_file()).cloned() else {
+            return Ok(());
+        };
+        let editor = Self::editor()?;
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().unwrap_or_default();
+
+        self.ui.suspend()?;
+        let status = process::Command::new(program)
+            .args(parts)
+            .arg(&path)
+            .status();
+        self.ui.resume()?;
+
+        match status {
+            Ok(status) if status.success() => {
+                app.info(format!("Closed {}", path.display()));
+                Ok(())
             }
+            Ok(status) => Err(AppError::EditorError(format!(
+                "'{}' exited with {}",
+                program, status
+            ))),
+            Err(e) => Err(AppError::EditorError(format!(
+                "failed to launch '{}': {}",
+                program, e
+            ))),
+        }
+    }
+}
+
+/// Saves what is on screen as plain text, for sharing the view in a bug report.
+pub struct ScreenshotCommand<'a> {
+    pub ui: &'a TerminalUI,
+}
+
+impl Command for ScreenshotCommand<'_> {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        fs::write(SCREENSHOT_FILE, self.ui.screenshot())?;
+        let path = absolute_path(Path::new(SCREENSHOT_FILE));
+        app.info(format!("Saved screenshot to {}", path.display()));
+        Ok(())
+    }
+}
+
+pub struct CopySummaryCommand;
+
+impl Command for CopySummaryCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let Some(view) = &app.analysis else {
+            app.error("Nothing to copy, analyze a directory first");
+            return Ok(());
+        };
+        let markdown = view.to_markdown();
+        match app
+            .clipboard
+            .copy(&markdown, "rust-code-analysis-summary.md")?
+        {
+            CopyTarget::Clipboard => app.info("Copied summary to the clipboard"),
+            CopyTarget::File(path) => app.info(format!(
+                "Clipboard unavailable, summary written to {}",
+                path.display()
+            )),
+        }
+        Ok(())
+    }
+}
+
+/// Copies the selected row of the focused Analysis or Details pane as `key: value` text, or
+/// the whole summary from the Files pane.
+pub struct CopyRowCommand;
+
+impl Command for CopyRowCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let fmt = &app.config.number_format;
+        let line = match app.view.focus {
+            Focus::Files => return CopySummaryCommand.execute(app),
+            Focus::Analysis => app.analysis.as_ref().and_then(AnalysisView::selected_line),
+            Focus::Details => match (&app.tree, &app.details) {
+                (Some(tree), _) => tree.selected_line(fmt),
+                (None, Some(details)) => details.line(app.view.details_row).map(str::to_owned),
+                (None, None) => None,
+            },
+        };
+        let Some(line) = line else {
+            app.info("Nothing to copy on this row");
+            return Ok(());
+        };
+        if app.clipboard.try_copy(&line) {
+            app.info(format!("Copied {}", line));
+        } else {
+            app.info(line);
         }
         Ok(())
     }
 }
+
+pub struct CopyPathCommand;
+
+impl Command for CopyPathCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let path = app
+            .navigator
+            .selected()
+            .unwrap_or(&app.navigator.current)
+            .clone();
+        let absolute = absolute_path(&path).display().to_string();
+        if app.clipboard.try_copy(&absolute) {
+            app.info(format!("Copied {}", absolute));
+        } else {
+            app.info(absolute);
+        }
+        Ok(())
+    }
+}
+
+pub struct SaveSnapshotCommand;
+
+impl Command for SaveSnapshotCommand {
+    fn execute(&mut self, app: &mut App) -> AppResult<()> {
+        let Some(view) = &app.analysis else {
+            app.error("Nothing to save, analyze a directory first");
+            return Ok(());
+        };
+        let root = view.root.clone();
+        app.history.record(&root, &view.groups[0].summary);
+        app.history.save()?;
+        let runs = app.history.runs(&root).len();
+        app.info(format!(
+            "Saved snapshot for {} ({} in history)",
+            root.display(),
+            runs
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    analysis::MetricsSummary,
+    error::{AppError, AppResult},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    const ALL: [(&'static str, Op); 6] = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+    ];
+
+    fn holds(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Op::Gt => lhs > rhs,
+            Op::Ge => lhs >= rhs,
+            Op::Lt => lhs < rhs,
+            Op::Le => lhs <= rhs,
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = Op::ALL
+            .iter()
+            .find(|(_, op)| op == self)
+            .map_or("?", |(s, _)| s);
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub metric: String,
+    pub field: String,
+    pub op: Op,
+    pub value: f64,
+}
+
+impl Condition {
+    pub fn actual(&self, summary: &MetricsSummary) -> AppResult<f64> {
+        summary
+            .values()
+            .get(&format!("{}.{}", self.metric, self.field))
+            .copied()
+            .ok_or_else(|| {
+                AppError::AnalysisError(format!(
+                    "No value for '{}.{}' in the summary",
+                    self.metric, self.field
+                ))
+            })
+    }
+
+    pub fn holds(&self, summary: &MetricsSummary) -> AppResult<bool> {
+        Ok(self.op.holds(self.actual(summary)?, self.value))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}{}{}", self.metric, self.field, self.op, self.value)
+    }
+}
+
+impl FromStr for Condition {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            AppError::InvalidArgument(format!(
+                "Invalid condition '{}', expected `metric.field <op> number`",
+                s
+            ))
+        };
+        let (lhs, op, rhs) = Op::ALL
+            .iter()
+            .find_map(|(symbol, op)| s.split_once(symbol).map(|(l, r)| (l, *op, r)))
+            .ok_or_else(invalid)?;
+        let (metric, field) = lhs.trim().split_once('.').ok_or_else(invalid)?;
+        if metric.is_empty() || field.is_empty() {
+            return Err(invalid());
+        }
+        let value = rhs.trim().parse::<f64>().map_err(|_| invalid())?;
+        Ok(Self {
+            metric: metric.to_owned(),
+            field: field.to_owned(),
+            op,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_operator_parses() {
+        for (symbol, op) in Op::ALL {
+            let condition: Condition = format!("cyclomatic.sum {} 10", symbol).parse().unwrap();
+            assert_eq!(condition.op, op, "{}", symbol);
+            assert_eq!(
+                (condition.metric.as_str(), condition.field.as_str()),
+                ("cyclomatic", "sum")
+            );
+            assert_eq!(condition.value, 10.0);
+        }
+        let condition: Condition = "mi.mi_original<=65.5".parse().unwrap();
+        assert_eq!(condition.op, Op::Le);
+        assert_eq!(condition.to_string(), "mi.mi_original<=65.5");
+    }
+
+    #[test]
+    fn malformed_conditions_are_rejected() {
+        for input in [
+            "",
+            "cyclomatic.sum",
+            "cyclomatic>1",
+            ".sum>1",
+            "cyclomatic.>1",
+            "loc.sloc>many",
+        ] {
+            assert!(input.parse::<Condition>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn unknown_metrics_fail_when_checked() {
+        let summary = MetricsSummary::default();
+        let condition: Condition = "nosuch.sum>1".parse().unwrap();
+        let err = condition.holds(&summary).unwrap_err().to_string();
+        assert!(err.contains("No value for 'nosuch.sum'"), "{}", err);
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use crate::{
+    analysis::MiVariant,
+    budget::Budgets,
+    custom::CustomMetric,
+    error::{AppError, AppResult},
+    format::{NumberFormat, TimestampFormat},
+    health::HealthWeights,
+    keymap::Action,
+    logger,
+    theme::Theme,
+    thresholds::Thresholds,
+};
+
+const APP_DIR: &str = "rust-code-analysis-tui";
+const CONFIG_FILE: &str = "config.json";
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+/// Reads a saved JSON state file, falling back to the default when it is missing. A file that
+/// can't be parsed is logged and copied to `<name>.bak` before the default is used, so that
+/// the next save doesn't destroy it.
+pub fn load_json<T: DeserializeOwned + Default>(path: Option<PathBuf>, what: &str) -> T {
+    let Some(path) = path else {
+        return T::default();
+    };
+    let content = match fs::read(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return T::default(),
+        Err(e) => {
+            logger::warn(format!("Failed to read {} {}: {}", what, path.display(), e));
+            return T::default();
+        }
+    };
+    serde_json::from_slice(&content).unwrap_or_else(|e| {
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        match fs::copy(&path, &backup) {
+            Ok(_) => logger::warn(format!(
+                "Ignoring invalid {} {}: {} (backed up to {})",
+                what,
+                path.display(),
+                e,
+                backup.display()
+            )),
+            Err(_) => logger::warn(format!(
+                "Ignoring invalid {} {}: {}",
+                what,
+                path.display(),
+                e
+            )),
+        }
+        T::default()
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub number_format: NumberFormat,
+    pub thresholds: Thresholds,
+    /// Single pass/fail targets, keyed like thresholds.
+    pub budgets: Budgets,
+    /// Rows kept visible above and below the selection in the file list.
+    pub scrolloff: usize,
+    pub theme: Theme,
+    /// Weights of the metrics in the health score; zero excludes a metric.
+    pub health: HealthWeights,
+    /// Extra metrics read from each file's metrics object by JSON pointer.
+    pub custom_metrics: Vec<CustomMetric>,
+    /// Key bindings overriding the defaults; a `null` action unbinds the key.
+    pub keys: BTreeMap<String, Option<Action>>,
+    pub timestamps: TimestampFormat,
+    /// Maintainability Index variant used as the headline, unless `--mi-variant` is given.
+    pub mi_variant: MiVariant,
+}
+
+impl Config {
+    fn file() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    pub fn load() -> Self {
+        load_json(Self::file(), "config")
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let path = Self::file()
+            .ok_or_else(|| AppError::ConfigError("no config directory available".into()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::ConfigError(format!("failed to encode config: {}", e)))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::Metrics;
+
+/// How the values of a custom metric are combined across files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Average,
+}
+
+impl Aggregate {
+    pub fn label(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "Sum",
+            Aggregate::Average => "Average",
+        }
+    }
+}
+
+/// A metric the tool doesn't model, read from each file's metrics object with a JSON pointer
+/// such as `/halstead/N1` or `/my_analyzer/score`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomMetric {
+    pub label: String,
+    pub pointer: String,
+    #[serde(default)]
+    pub aggregate: Aggregate,
+}
+
+impl CustomMetric {
+    /// The numeric value at the pointer, or `None` when it is missing or not a number.
+    pub fn extract(&self, metrics: &serde_json::Value) -> Option<f64> {
+        metrics.pointer(&self.pointer)?.as_f64()
+    }
+}
+
+/// The metrics object of a file as raw JSON, including the fields kept outside the typed model.
+pub fn raw_metrics(metrics: &Metrics) -> serde_json::Value {
+    serde_json::to_value(metrics).unwrap_or_default()
+}
@@ -11,6 +11,14 @@ pub enum AppError {
     TerminalError(String),
     #[error("Analysis error: {0}")]
     AnalysisError(String),
+    #[error("Analysis cancelled")]
+    Cancelled,
+    #[error("Config error: {0}")]
+    ConfigError(String),
+    #[error("Editor error: {0}")]
+    EditorError(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
 }
 
 pub type AppResult<T> = Result<T, AppError>;
@@ -0,0 +1,240 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, Utc,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path, time::SystemTime};
+
+/// Which rows of a metric block that has both totals and averages are shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueMode {
+    #[default]
+    Both,
+    Totals,
+    Averages,
+}
+
+impl ValueMode {
+    pub fn next(self) -> Self {
+        match self {
+            ValueMode::Both => ValueMode::Totals,
+            ValueMode::Totals => ValueMode::Averages,
+            ValueMode::Averages => ValueMode::Both,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ValueMode::Both => "totals and averages",
+            ValueMode::Totals => "totals",
+            ValueMode::Averages => "averages",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberFormat {
+    pub decimals: usize,
+    /// Decimal places of single metrics, overriding `decimals`: a whole family such as `mi`,
+    /// or one field such as `halstead.purity_ratio`, which wins over its family.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub precision: BTreeMap<String, usize>,
+    pub grouping: bool,
+    /// Show breakdown counts as a percentage of their section's total. Toggled at runtime
+    /// rather than configured.
+    #[serde(skip)]
+    pub percentages: bool,
+    /// Totals, averages or both, for every block that has both. Toggled at runtime too.
+    #[serde(skip)]
+    pub values: ValueMode,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimals: 2,
+            precision: BTreeMap::new(),
+            grouping: false,
+            percentages: false,
+            values: ValueMode::default(),
+        }
+    }
+}
+
+impl NumberFormat {
+    pub fn float(&self, value: f64) -> String {
+        self.fixed(value, self.decimals)
+    }
+
+    /// The same format with a fixed number of decimals, whatever the metric.
+    pub fn with_decimals(&self, decimals: usize) -> Self {
+        Self {
+            decimals,
+            precision: BTreeMap::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Formats the value of a metric given by its full key, such as `mi.mi_original`, with its
+    /// configured precision if any.
+    pub fn metric(&self, key: Option<&str>, value: Option<f64>) -> String {
+        let decimals = key.map_or(self.decimals, |key| self.decimals_of(key));
+        value.map_or("N/A".into(), |v| self.fixed(v, decimals))
+    }
+
+    fn decimals_of(&self, key: &str) -> usize {
+        let family = key.split('.').next().unwrap_or(key);
+        self.precision
+            .get(key)
+            .or_else(|| self.precision.get(family))
+            .copied()
+            .unwrap_or(self.decimals)
+    }
+
+    fn fixed(&self, value: f64, decimals: usize) -> String {
+        if !is_displayable(value) {
+            return "N/A".into();
+        }
+        let formatted = format!("{:.*}", decimals, value);
+        if self.grouping {
+            group_thousands(&formatted)
+        } else {
+            formatted
+        }
+    }
+
+    pub fn count(&self, value: usize) -> String {
+        let formatted = value.to_string();
+        if self.grouping {
+            group_thousands(&formatted)
+        } else {
+            formatted
+        }
+    }
+
+    pub fn optional(&self, value: Option<f64>) -> String {
+        value.map_or("N/A".into(), |v| self.float(v))
+    }
+
+    /// Formats a breakdown count, as a share of `total` when percentages are shown.
+    pub fn breakdown(&self, count: f64, total: f64) -> String {
+        if !self.percentages {
+            return self.float(count);
+        }
+        if total == 0.0 {
+            return "N/A".into();
+        }
+        format!("{}%", self.float(count / total * 100.0))
+    }
+}
+
+const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// How the dates of history snapshots and the times of log entries are shown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimestampFormat {
+    /// A `chrono` strftime format such as `%d/%m %H:%M`; an invalid one falls back to the
+    /// default.
+    pub format: String,
+    /// Show UTC rather than the local time zone.
+    pub utc: bool,
+}
+
+impl Default for TimestampFormat {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_TIMESTAMP_FORMAT.to_owned(),
+            utc: false,
+        }
+    }
+}
+
+impl TimestampFormat {
+    pub fn date_time(&self, time: SystemTime) -> String {
+        self.render(time, &self.format)
+    }
+
+    pub fn time_of_day(&self, time: SystemTime) -> String {
+        self.render(time, "%H:%M:%S")
+    }
+
+    fn render(&self, time: SystemTime, format: &str) -> String {
+        let format = if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            DEFAULT_TIMESTAMP_FORMAT
+        } else {
+            format
+        };
+        let time = DateTime::<Utc>::from(time);
+        if self.utc || !local_offset_known() {
+            format!("{} UTC", time.format(format))
+        } else {
+            time.with_timezone(&Local).format(format).to_string()
+        }
+    }
+}
+
+/// Whether the local time zone can be found. Without one chrono quietly uses UTC, so the
+/// timestamps are labelled as UTC instead of passing for local time.
+fn local_offset_known() -> bool {
+    cfg!(not(unix)) || std::env::var_os("TZ").is_some() || Path::new("/etc/localtime").exists()
+}
+
+fn is_displayable(value: f64) -> bool {
+    value.is_finite() && value.abs() < f64::MAX
+}
+
+fn group_thousands(number: &str) -> String {
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    let (integer, fraction) = match unsigned.find('.') {
+        Some(dot) => unsigned.split_at(dot),
+        None => (unsigned, ""),
+    };
+    let mut grouped = String::with_capacity(number.len() + integer.len() / 3);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+/// Drops the first `offset` characters of `text`, for horizontally scrolled cells.
+pub fn scroll_text(text: &str, offset: usize) -> String {
+    text.chars().skip(offset).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    /// 2021-01-02 03:04:05 UTC.
+    fn moment() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_609_556_645)
+    }
+
+    fn utc(format: &str) -> TimestampFormat {
+        TimestampFormat {
+            format: format.to_owned(),
+            utc: true,
+        }
+    }
+
+    #[test]
+    fn utc_timestamps_are_labelled() {
+        assert_eq!(utc("%d/%m %H:%M").date_time(moment()), "02/01 03:04 UTC");
+        assert_eq!(utc("%d/%m").time_of_day(moment()), "03:04:05 UTC");
+    }
+
+    #[test]
+    fn invalid_formats_fall_back_to_the_default() {
+        assert_eq!(utc("%Q %d").date_time(moment()), "2021-01-02 03:04 UTC");
+        assert_eq!(utc("%").date_time(moment()), "2021-01-02 03:04 UTC");
+    }
+}
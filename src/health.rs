@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{analysis::MetricsSummary, thresholds::Thresholds};
+
+/// Per-function values at which a lower-is-better metric scores 50 out of 100.
+const REFERENCES: [(&str, f64); 4] = [
+    ("cyclomatic.average", 10.0),
+    ("cognitive.average", 15.0),
+    ("halstead.bugs", 0.1),
+    ("abc.magnitude", 10.0),
+];
+
+/// Upper bound of each Maintainability Index variant, used to map it onto 0–100.
+const MI_RANGES: [(&str, f64); 3] = [
+    ("mi.mi_original", 171.0),
+    ("mi.mi_sei", 171.0),
+    ("mi.mi_visual_studio", 100.0),
+];
+
+/// How much each metric contributes to the health score, keyed like thresholds. A metric
+/// with a weight of zero, or left out, doesn't contribute.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HealthWeights {
+    weights: BTreeMap<String, f64>,
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        let weights = [
+            ("mi.mi_original", 40.0),
+            ("cyclomatic.average", 20.0),
+            ("cognitive.average", 20.0),
+            ("halstead.bugs", 10.0),
+            ("abc.magnitude", 10.0),
+        ];
+        Self {
+            weights: weights
+                .into_iter()
+                .map(|(key, weight)| (key.to_owned(), weight))
+                .collect(),
+        }
+    }
+}
+
+impl HealthWeights {
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.weights
+            .iter()
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(key, weight)| (key.as_str(), *weight))
+    }
+}
+
+/// Scores a single metric from 0 (worst) to 100 (best). The Maintainability Index is scaled
+/// from its range; other metrics score 50 at their reference value, which is the built-in
+/// one or else the metric's warning threshold.
+fn metric_score(key: &str, value: f64, thresholds: &Thresholds) -> Option<f64> {
+    if let Some((_, max)) = MI_RANGES.iter().find(|(mi, _)| *mi == key) {
+        return Some((value / max * 100.0).clamp(0.0, 100.0));
+    }
+    let reference = REFERENCES
+        .iter()
+        .find(|(metric, _)| *metric == key)
+        .map(|(_, reference)| *reference)
+        .or(thresholds.get(key).warning)
+        .filter(|reference| *reference > 0.0)?;
+    Some(100.0 / (1.0 + value.max(0.0) / reference))
+}
+
+/// A 0–100 composite of the weighted metrics, computed from per-function values so that
+/// projects of any size compare fairly. Weights are renormalized over the metrics that are
+/// present, so excluding one keeps the score on the same scale.
+pub fn health_score(
+    summary: &MetricsSummary,
+    weights: &HealthWeights,
+    thresholds: &Thresholds,
+) -> Option<f64> {
+    let values = summary.per_function().values();
+    let (total, weight) = summary
+        .mi_variant()
+        .resolve(weights.iter())
+        .into_iter()
+        .filter_map(|(key, weight)| {
+            let value = values.get(key).filter(|value| value.is_finite())?;
+            metric_score(key, *value, thresholds).map(|score| (score, weight))
+        })
+        .fold((0.0, 0.0), |(total, sum), (score, weight)| {
+            (total + score * weight, sum + weight)
+        });
+    (weight > 0.0).then(|| total / weight)
+}
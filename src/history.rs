@@ -0,0 +1,219 @@
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    widgets::{Cell, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    analysis::MetricsSummary,
+    config::{config_dir, load_json},
+    error::{AppError, AppResult},
+    format::{NumberFormat, TimestampFormat},
+};
+
+const HISTORY_FILE: &str = "history.json";
+const MAX_RUNS_PER_DIRECTORY: usize = 50;
+
+pub const TRACKED_METRICS: [(&str, &str); 9] = [
+    ("Cyclomatic Sum", "cyclomatic.sum"),
+    ("Cognitive Sum", "cognitive.sum"),
+    ("Halstead Volume", "halstead.volume"),
+    ("Halstead Bugs", "halstead.bugs"),
+    ("SLOC", "loc.sloc"),
+    ("Functions", "nom.total"),
+    ("MI Original", "mi.mi_original"),
+    ("ABC Branches", "abc.branches"),
+    ("ABC Magnitude", "abc.magnitude"),
+];
+
+/// Number of most recent runs drawn in the summary's trend column.
+pub const SPARKLINE_RUNS: usize = 8;
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A block-character sparkline of `key` over the last runs, or `None` when fewer than two of
+/// them recorded it.
+pub fn sparkline(runs: &[HistoryEntry], key: &str) -> Option<String> {
+    let values: Vec<f64> = runs
+        .iter()
+        .filter_map(|run| run.values.get(key).copied())
+        .collect();
+    let values = &values[values.len().saturating_sub(SPARKLINE_RUNS)..];
+    if values.len() < 2 {
+        return None;
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let top = SPARK_BLOCKS.len() - 1;
+    Some(
+        values
+            .iter()
+            .map(|value| {
+                let level = if max > min {
+                    ((value - min) / (max - min) * top as f64).round() as usize
+                } else {
+                    top / 2
+                };
+                SPARK_BLOCKS[level.min(top)]
+            })
+            .collect(),
+    )
+}
+
+pub fn higher_is_worse(key: &str) -> bool {
+    !key.starts_with("mi.")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
+
+impl Trend {
+    pub fn between(previous: f64, latest: f64) -> Self {
+        if latest > previous {
+            Trend::Up
+        } else if latest < previous {
+            Trend::Down
+        } else {
+            Trend::Flat
+        }
+    }
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Trend::Up => "▲",
+            Trend::Down => "▼",
+            Trend::Flat => "=",
+        }
+    }
+
+    pub fn is_regression(self, key: &str) -> bool {
+        match self {
+            Trend::Up => higher_is_worse(key),
+            Trend::Down => !higher_is_worse(key),
+            Trend::Flat => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub values: BTreeMap<String, f64>,
+}
+
+impl HistoryEntry {
+    pub fn label(&self, timestamps: &TimestampFormat) -> String {
+        timestamps.date_time(UNIX_EPOCH + Duration::from_secs(self.timestamp))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct History {
+    runs: BTreeMap<String, Vec<HistoryEntry>>,
+}
+
+impl History {
+    fn file() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(HISTORY_FILE))
+    }
+
+    pub fn load() -> Self {
+        load_json(Self::file(), "history")
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let path = Self::file()
+            .ok_or_else(|| AppError::ConfigError("no config directory available".into()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string(self)
+            .map_err(|e| AppError::ConfigError(format!("failed to encode history: {}", e)))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, dir: &Path, summary: &MetricsSummary) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let runs = self.runs.entry(dir.display().to_string()).or_default();
+        runs.push(HistoryEntry {
+            timestamp,
+            values: summary.values(),
+        });
+        if runs.len() > MAX_RUNS_PER_DIRECTORY {
+            runs.drain(..runs.len() - MAX_RUNS_PER_DIRECTORY);
+        }
+    }
+
+    pub fn runs(&self, dir: &Path) -> &[HistoryEntry] {
+        self.runs
+            .get(&dir.display().to_string())
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+pub fn create_history_table(
+    runs: &[HistoryEntry],
+    shown: usize,
+    fmt: &NumberFormat,
+    timestamps: &TimestampFormat,
+) -> Table<'static> {
+    let runs = &runs[runs.len().saturating_sub(shown)..];
+
+    let mut header = vec![Cell::from("Metric")];
+    header.extend(runs.iter().map(|run| Cell::from(run.label(timestamps))));
+    header.push(Cell::from("Trend"));
+
+    let rows: Vec<Row> = TRACKED_METRICS
+        .iter()
+        .map(|(label, key)| {
+            let mut cells = vec![Cell::from(*label).style(Style::default().fg(Color::Yellow))];
+            cells.extend(
+                runs.iter()
+                    .map(|run| Cell::from(fmt.metric(Some(key), run.values.get(*key).copied()))),
+            );
+            let latest = runs.last().and_then(|run| run.values.get(*key));
+            let previous = runs
+                .len()
+                .checked_sub(2)
+                .and_then(|i| runs[i].values.get(*key));
+            let trend = match (previous, latest) {
+                (Some(previous), Some(latest)) => {
+                    let trend = Trend::between(*previous, *latest);
+                    let color = if trend.is_regression(key) {
+                        Color::Red
+                    } else if trend == Trend::Flat {
+                        Color::Gray
+                    } else {
+                        Color::Green
+                    };
+                    Cell::from(trend.arrow()).style(Style::default().fg(color))
+                }
+                _ => Cell::from(""),
+            };
+            cells.push(trend);
+            Row::new(cells)
+        })
+        .collect();
+
+    let mut widths = vec![Constraint::Length(16)];
+    widths.extend(runs.iter().map(|_| Constraint::Length(16)));
+    widths.push(Constraint::Length(5));
+
+    Table::new(rows, widths)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .column_spacing(1)
+}
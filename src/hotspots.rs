@@ -0,0 +1,307 @@
+use ratatui::{
+    layout::{Constraint, Direction},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Cell, Row, Table},
+};
+use serde::Serialize;
+use std::{
+    cmp::Ordering,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    error::AppError,
+    format::{scroll_text, NumberFormat},
+    metrics::{JsonData, Metrics, Space},
+    navigator::file_name,
+};
+
+pub const DEFAULT_TOP: usize = 20;
+const FUNCTION_KIND: &str = "function";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HotspotMetric {
+    #[default]
+    Cognitive,
+    Cyclomatic,
+    Sloc,
+    Nargs,
+    Nexits,
+    HalsteadEffort,
+}
+
+impl HotspotMetric {
+    pub const ALL: [HotspotMetric; 6] = [
+        HotspotMetric::Cognitive,
+        HotspotMetric::Cyclomatic,
+        HotspotMetric::Sloc,
+        HotspotMetric::Nargs,
+        HotspotMetric::Nexits,
+        HotspotMetric::HalsteadEffort,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            HotspotMetric::Cognitive => "cognitive",
+            HotspotMetric::Cyclomatic => "cyclomatic",
+            HotspotMetric::Sloc => "sloc",
+            HotspotMetric::Nargs => "nargs",
+            HotspotMetric::Nexits => "nexits",
+            HotspotMetric::HalsteadEffort => "effort",
+        }
+    }
+
+    /// The full key of the value ranked, as used by thresholds and `number_format.precision`.
+    pub fn summary_key(self) -> &'static str {
+        match self {
+            HotspotMetric::Cognitive => "cognitive.sum",
+            HotspotMetric::Cyclomatic => "cyclomatic.sum",
+            HotspotMetric::Sloc => "loc.sloc",
+            HotspotMetric::Nargs => "nargs.total",
+            HotspotMetric::Nexits => "nexits.sum",
+            HotspotMetric::HalsteadEffort => "halstead.effort",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HotspotMetric::Cognitive => "Cognitive",
+            HotspotMetric::Cyclomatic => "Cyclomatic",
+            HotspotMetric::Sloc => "SLOC",
+            HotspotMetric::Nargs => "NArgs",
+            HotspotMetric::Nexits => "NExits",
+            HotspotMetric::HalsteadEffort => "Halstead Effort",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|m| *m == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    fn value(self, metrics: &Metrics) -> Option<f64> {
+        match self {
+            HotspotMetric::Cognitive => metrics.cognitive.as_ref().and_then(|m| m.sum),
+            HotspotMetric::Cyclomatic => metrics.cyclomatic.as_ref().and_then(|m| m.sum),
+            HotspotMetric::Sloc => metrics.loc.as_ref().and_then(|m| m.sloc),
+            HotspotMetric::Nargs => metrics.nargs.as_ref().and_then(|m| m.total),
+            HotspotMetric::Nexits => metrics.nexits.as_ref().and_then(|m| m.sum),
+            HotspotMetric::HalsteadEffort => metrics.halstead.as_ref().and_then(|m| m.effort),
+        }
+    }
+}
+
+impl fmt::Display for HotspotMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl FromStr for HotspotMetric {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|metric| metric.key() == s)
+            .ok_or_else(|| {
+                let known: Vec<_> = Self::ALL.iter().map(|m| m.key()).collect();
+                AppError::InvalidArgument(format!(
+                    "Unknown hotspot metric '{}', expected one of {}",
+                    s,
+                    known.join(", ")
+                ))
+            })
+    }
+}
+
+/// A single function retained from the analysis, with every rankable metric.
+#[derive(Debug, Clone)]
+pub struct FunctionRecord {
+    pub file: String,
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    values: [Option<f64>; HotspotMetric::ALL.len()],
+}
+
+impl FunctionRecord {
+    pub fn value(&self, metric: HotspotMetric) -> Option<f64> {
+        self.values[metric.index()]
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hotspot {
+    pub file: String,
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub value: f64,
+}
+
+impl Hotspot {
+    pub fn location(&self) -> String {
+        format!("{}:{}-{}", self.file, self.start_line, self.end_line)
+    }
+
+    /// The source file this hotspot was measured in. The metrics name it as it was given to
+    /// `rust-code-analysis-cli`, usually relative to the project, and the metrics are often
+    /// written inside that project, so a relative name is looked up from `root` upwards.
+    pub fn source_path(&self, root: &Path) -> Option<PathBuf> {
+        let file = Path::new(&self.file);
+        if file.is_absolute() {
+            return file.is_file().then(|| file.to_path_buf());
+        }
+        root.ancestors()
+            .map(|dir| dir.join(file))
+            .find(|path| path.is_file())
+    }
+}
+
+pub fn collect_functions(data: &[JsonData]) -> Vec<FunctionRecord> {
+    let mut records = Vec::new();
+    for file in data {
+        collect_spaces(&file.name, &file.spaces, &mut records);
+    }
+    records
+}
+
+/// One record per file, from its file-level metrics, so files can be ranked like functions.
+pub fn collect_files(data: &[JsonData]) -> Vec<FunctionRecord> {
+    data.iter()
+        .filter_map(|file| {
+            let metrics = file.metrics.as_ref()?;
+            Some(FunctionRecord {
+                file: file.name.clone(),
+                name: file.name.clone(),
+                start_line: file.start_line,
+                end_line: file.end_line,
+                values: HotspotMetric::ALL.map(|metric| metric.value(metrics)),
+            })
+        })
+        .collect()
+}
+
+/// A horizontal bar per hotspot, labelled with its file name and scaled to the largest value.
+pub fn create_hotspots_chart(
+    hotspots: &[Hotspot],
+    metric: HotspotMetric,
+    fmt: &NumberFormat,
+) -> BarChart<'static> {
+    let bars: Vec<Bar> = hotspots
+        .iter()
+        .map(|hotspot| {
+            Bar::default()
+                .label(Line::from(file_name(Path::new(&hotspot.file)).into_owned()))
+                .value(hotspot.value.max(0.0).round() as u64)
+                .text_value(fmt.metric(Some(metric.summary_key()), Some(hotspot.value)))
+        })
+        .collect();
+    BarChart::default()
+        .direction(Direction::Horizontal)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(Color::Yellow))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+}
+
+fn collect_spaces(file: &str, spaces: &[Space], records: &mut Vec<FunctionRecord>) {
+    for space in spaces {
+        if space.kind == FUNCTION_KIND {
+            if let Some(metrics) = &space.metrics {
+                records.push(FunctionRecord {
+                    file: file.to_owned(),
+                    name: space.name.clone(),
+                    start_line: space.start_line,
+                    end_line: space.end_line,
+                    values: HotspotMetric::ALL.map(|metric| metric.value(metrics)),
+                });
+            }
+        }
+        collect_spaces(file, &space.spaces, records);
+    }
+}
+
+/// Returns the `n` functions with the highest value of `metric`, worst first.
+pub fn top_functions(records: &[FunctionRecord], metric: HotspotMetric, n: usize) -> Vec<Hotspot> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut ranked: Vec<(&FunctionRecord, f64)> = records
+        .iter()
+        .filter_map(|record| {
+            record
+                .value(metric)
+                .filter(|v| v.is_finite())
+                .map(|v| (record, v))
+        })
+        .collect();
+    let by_value_desc = |a: &(&FunctionRecord, f64), b: &(&FunctionRecord, f64)| {
+        b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal)
+    };
+    if ranked.len() > n {
+        ranked.select_nth_unstable_by(n - 1, by_value_desc);
+        ranked.truncate(n);
+    }
+    ranked.sort_by(by_value_desc);
+    ranked
+        .into_iter()
+        .map(|(record, value)| Hotspot {
+            file: record.file.clone(),
+            name: record.name.clone(),
+            start_line: record.start_line,
+            end_line: record.end_line,
+            value,
+        })
+        .collect()
+}
+
+pub fn create_hotspots_table(
+    hotspots: &[Hotspot],
+    metric: HotspotMetric,
+    fmt: &NumberFormat,
+    offset: usize,
+) -> Table<'static> {
+    let rows: Vec<Row> = hotspots
+        .iter()
+        .map(|hotspot| {
+            Row::new(vec![
+                Cell::from(fmt.metric(Some(metric.summary_key()), Some(hotspot.value)))
+                    .style(Style::default().fg(Color::Yellow)),
+                Cell::from(scroll_text(&hotspot.name, offset)),
+                Cell::from(scroll_text(&hotspot.location(), offset)),
+            ])
+        })
+        .collect();
+
+    Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Percentage(30),
+            Constraint::Percentage(70),
+        ],
+    )
+    .header(
+        Row::new(vec![metric.label(), "Function", "Location"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::Blue)
+            .add_modifier(Modifier::BOLD),
+    )
+    .column_spacing(1)
+}
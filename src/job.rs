@@ -0,0 +1,120 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+use crate::{
+    analysis::{self, SummaryOptions},
+    analysis_view::AnalysisView,
+    error::{AppError, AppResult},
+    format::NumberFormat,
+    navigator::{common_ancestor, root_name},
+};
+
+pub struct AnalysisJob {
+    pub path: PathBuf,
+    pub roots: Vec<PathBuf>,
+    cancel: Arc<AtomicBool>,
+    receiver: Receiver<AppResult<AnalysisView>>,
+}
+
+fn analyze(
+    root: &Path,
+    roots: &[PathBuf],
+    options: &SummaryOptions,
+    format: NumberFormat,
+    cancel: &AtomicBool,
+) -> AppResult<AnalysisView> {
+    if let [path] = roots {
+        let scan = analysis::read_path(path, options, cancel)?;
+        let mut view = AnalysisView::from_data(path, &scan.data, options, format);
+        view.ignored = scan.ignored;
+        return Ok(view);
+    }
+    let mut ignored = 0;
+    let mut data = Vec::with_capacity(roots.len());
+    for path in roots {
+        let scan = analysis::read_path(path, options, cancel)?;
+        ignored += scan.ignored;
+        data.push((root_name(root, path), scan.data));
+    }
+    let mut view = AnalysisView::from_roots(root, data, options, format);
+    view.ignored = ignored;
+    Ok(view)
+}
+
+impl AnalysisJob {
+    pub fn spawn(roots: &[PathBuf], options: SummaryOptions, format: NumberFormat) -> Self {
+        let path = match roots {
+            [path] => path.clone(),
+            _ => common_ancestor(roots),
+        };
+        let (root, paths) = (path.clone(), roots.to_vec());
+        Self::run(path, roots.to_vec(), move |cancel| {
+            analyze(&root, &paths, &options, format, cancel)
+        })
+    }
+
+    /// Analyzes exactly `files`, as listed by `--files-from`, presenting them under `root`.
+    pub fn spawn_files(
+        root: &Path,
+        files: Vec<PathBuf>,
+        options: SummaryOptions,
+        format: NumberFormat,
+    ) -> Self {
+        let view_root = root.to_path_buf();
+        Self::run(
+            root.to_path_buf(),
+            vec![root.to_path_buf()],
+            move |cancel| {
+                let scan = analysis::scan_files(&files, &options, cancel)?;
+                Ok(AnalysisView::from_data(
+                    &view_root, &scan.data, &options, format,
+                ))
+            },
+        )
+    }
+
+    fn run<F>(path: PathBuf, roots: Vec<PathBuf>, work: F) -> Self
+    where
+        F: FnOnce(&AtomicBool) -> AppResult<AnalysisView> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&cancel);
+        thread::spawn(move || {
+            let started = Instant::now();
+            let result = work(&flag).map(|mut view| {
+                view.elapsed = started.elapsed();
+                view
+            });
+            let _ = sender.send(result);
+        });
+        Self {
+            path,
+            roots,
+            cancel,
+            receiver,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn poll(&self) -> Option<AppResult<AnalysisView>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(AppError::AnalysisError(
+                "analysis worker stopped unexpectedly".into(),
+            ))),
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+
+use crate::{error::AppError, logger};
+
+/// Everything a key can be bound to outside popups, prompts and the file filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    /// Quits and prints the current directory, for shell wrappers that `cd` into it.
+    QuitToPath,
+    NextFocus,
+    FocusAnalysis,
+    FocusFiles,
+    FocusDetails,
+    Search,
+    GoTo,
+    ResetView,
+    GrowDetails,
+    ShrinkDetails,
+    TogglePresentation,
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Opens the selected entry, or expands the selected row of the Analysis or Details pane.
+    Activate,
+    /// Expands or collapses the selected row of the Analysis or Details pane.
+    Toggle,
+    Parent,
+    /// Opens the selected directory in the Files pane without analyzing it.
+    Browse,
+    Reload,
+    ToggleLog,
+    ToggleHidden,
+    FollowSymlinks,
+    Bookmark,
+    Bookmarks,
+    Recents,
+    NextGroup,
+    PreviousGroup,
+    NextFlagged,
+    PreviousFlagged,
+    PerFunction,
+    Percentages,
+    /// Cycles the summary between totals and averages, totals only, and averages only.
+    Values,
+    Compact,
+    Thresholds,
+    Snapshot,
+    History,
+    Hotspots,
+    Chart,
+    CopyPath,
+    CopySummary,
+    /// Copies the selected row of the focused pane, or the summary from the Files pane.
+    CopyRow,
+    Compare,
+    Explain,
+    /// Lists the subdirectories of the analyzed tree, each with its own summary.
+    Directories,
+    /// Saves the screen as text to `screenshot.txt`.
+    Screenshot,
+    OpenInEditor,
+}
+
+const DEFAULT_BINDINGS: [(&str, Action); 50] = [
+    ("q", Action::Quit),
+    ("Q", Action::QuitToPath),
+    ("tab", Action::NextFocus),
+    ("1", Action::FocusAnalysis),
+    ("2", Action::FocusFiles),
+    ("3", Action::FocusDetails),
+    ("/", Action::Search),
+    ("g", Action::GoTo),
+    (":", Action::GoTo),
+    ("0", Action::ResetView),
+    ("+", Action::GrowDetails),
+    ("-", Action::ShrinkDetails),
+    ("P", Action::TogglePresentation),
+    ("up", Action::Up),
+    ("down", Action::Down),
+    ("left", Action::Left),
+    ("right", Action::Right),
+    ("enter", Action::Activate),
+    ("space", Action::Toggle),
+    ("backspace", Action::Parent),
+    ("o", Action::Browse),
+    ("r", Action::Reload),
+    ("l", Action::ToggleLog),
+    (".", Action::ToggleHidden),
+    ("L", Action::FollowSymlinks),
+    ("b", Action::Bookmark),
+    ("'", Action::Bookmarks),
+    ("R", Action::Recents),
+    ("ctrl-r", Action::Recents),
+    ("]", Action::NextGroup),
+    ("[", Action::PreviousGroup),
+    ("n", Action::NextFlagged),
+    ("N", Action::PreviousFlagged),
+    ("p", Action::PerFunction),
+    ("%", Action::Percentages),
+    ("A", Action::Values),
+    ("m", Action::Compact),
+    ("t", Action::Thresholds),
+    ("s", Action::Snapshot),
+    ("h", Action::History),
+    ("w", Action::Hotspots),
+    ("v", Action::Chart),
+    ("c", Action::CopyPath),
+    ("y", Action::CopyRow),
+    ("Y", Action::CopySummary),
+    ("a", Action::Compare),
+    ("x", Action::Explain),
+    ("D", Action::Directories),
+    ("S", Action::Screenshot),
+    ("E", Action::OpenInEditor),
+];
+
+/// A key with the modifiers that tell bindings apart. Shift is left out, since it is already
+/// part of the character (`R` rather than `shift-r`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            modifiers: modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+}
+
+impl From<&KeyEvent> for KeyBinding {
+    fn from(event: &KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = AppError;
+
+    /// Parses descriptors such as `q`, `R`, `ctrl-r`, `alt-enter`, `space`, `pagedown` or `f5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::InvalidArgument(format!("Unknown key '{}'", s));
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if let Some(key) = lower.strip_prefix("ctrl-").filter(|key| !key.is_empty()) {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[rest.len() - key.len()..];
+            } else if let Some(key) = lower.strip_prefix("alt-").filter(|key| !key.is_empty()) {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[rest.len() - key.len()..];
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => name
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (1..=12).contains(n))
+                    .map(KeyCode::F)
+                    .ok_or_else(invalid)?,
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// Maps keys to actions: the built-in bindings, overridden by the `keys` config map.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .filter_map(|(key, action)| Some((key.parse().ok()?, *action)))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Applies `overrides` on top of the defaults; a `null` action unbinds the key. Keys that
+    /// can't be parsed are logged and ignored.
+    pub fn new(overrides: &BTreeMap<String, Option<Action>>) -> Self {
+        let mut keymap = Self::default();
+        for (key, action) in overrides {
+            let binding = match key.parse() {
+                Ok(binding) => binding,
+                Err(e) => {
+                    logger::warn(format!("Ignoring key binding: {}", e));
+                    continue;
+                }
+            };
+            match action {
+                Some(action) => keymap.bindings.insert(binding, *action),
+                None => keymap.bindings.remove(&binding),
+            };
+        }
+        keymap
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&KeyBinding::from(event)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        KeyBinding::new(code, modifiers)
+    }
+
+    fn key(s: &str) -> KeyBinding {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn characters_keep_their_case() {
+        assert_eq!(key("q"), binding(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(key("R"), binding(KeyCode::Char('R'), KeyModifiers::NONE));
+        assert_eq!(key("-"), binding(KeyCode::Char('-'), KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn modifiers_are_parsed_in_any_case_and_order() {
+        let ctrl_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        assert_eq!(
+            key("ctrl-r"),
+            binding(KeyCode::Char('r'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(key("Alt-Enter"), binding(KeyCode::Enter, KeyModifiers::ALT));
+        assert_eq!(key("ctrl-alt-x"), binding(KeyCode::Char('x'), ctrl_alt));
+        assert_eq!(key("alt-ctrl-x"), key("ctrl-alt-x"));
+        // A lone `-` after a modifier is the key itself.
+        assert_eq!(
+            key("ctrl--"),
+            binding(KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn named_keys_and_function_keys() {
+        assert_eq!(
+            key("space"),
+            binding(KeyCode::Char(' '), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            key("PageDown"),
+            binding(KeyCode::PageDown, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            key("backtab"),
+            binding(KeyCode::BackTab, KeyModifiers::NONE)
+        );
+        assert_eq!(key("f1"), binding(KeyCode::F(1), KeyModifiers::NONE));
+        assert_eq!(key("F12"), binding(KeyCode::F(12), KeyModifiers::NONE));
+        for invalid in ["f0", "f13", "", "ctrl-", "dd", "hyper-x"] {
+            assert!(invalid.parse::<KeyBinding>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn shift_is_part_of_the_character() {
+        let event = KeyEvent::new(KeyCode::Char('R'), KeyModifiers::SHIFT);
+        assert_eq!(KeyBinding::from(&event), key("R"));
+    }
+
+    #[test]
+    fn overrides_rebind_unbind_and_skip_unknown_keys() {
+        let overrides = BTreeMap::from([
+            ("j".to_owned(), Some(Action::Down)),
+            ("q".to_owned(), None),
+            ("hyper-q".to_owned(), Some(Action::Quit)),
+        ]);
+        let keymap = Keymap::new(&overrides);
+        let press = |code| keymap.action(&KeyEvent::new(code, KeyModifiers::NONE));
+        assert_eq!(press(KeyCode::Char('j')), Some(Action::Down));
+        assert_eq!(press(KeyCode::Char('q')), None);
+        assert_eq!(press(KeyCode::Char('Q')), Some(Action::QuitToPath));
+        assert!(logger::recent(usize::MAX)
+            .iter()
+            .any(|entry| entry.message.starts_with("Ignoring key binding")
+                && entry.message.ends_with("Unknown key 'hyper-q'")));
+    }
+
+    #[test]
+    fn every_default_binding_parses() {
+        assert_eq!(Keymap::default().bindings.len(), DEFAULT_BINDINGS.len());
+    }
+}
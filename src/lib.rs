@@ -1,6 +1,26 @@
 pub mod analysis;
+pub mod analysis_view;
+pub mod app;
+pub mod bookmarks;
+pub mod budget;
+pub mod cli;
+pub mod clipboard;
 pub mod command;
+pub mod condition;
+pub mod config;
+pub mod custom;
 pub mod error;
+pub mod format;
+pub mod health;
+pub mod history;
+pub mod hotspots;
+pub mod job;
+pub mod keymap;
+pub mod logger;
 pub mod metrics;
 pub mod navigator;
+pub mod recents;
+pub mod space_tree;
+pub mod theme;
+pub mod thresholds;
 pub mod ui;
@@ -0,0 +1,50 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use crate::format::TimestampFormat;
+
+const MAX_ENTRIES: usize = 1000;
+
+static ENTRIES: Mutex<Vec<LogEntry>> = Mutex::new(Vec::new());
+static ECHO: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: SystemTime,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn timestamp(&self, timestamps: &TimestampFormat) -> String {
+        timestamps.time_of_day(self.time)
+    }
+}
+
+pub fn set_echo(echo: bool) {
+    ECHO.store(echo, Ordering::Relaxed);
+}
+
+pub fn warn(message: impl Into<String>) {
+    let message = message.into();
+    if ECHO.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    }
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    if entries.len() >= MAX_ENTRIES {
+        entries.remove(0);
+    }
+    entries.push(LogEntry {
+        time: SystemTime::now(),
+        message,
+    });
+}
+
+pub fn recent(limit: usize) -> Vec<LogEntry> {
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    entries[entries.len().saturating_sub(limit)..].to_vec()
+}
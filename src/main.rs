@@ -1,78 +1,978 @@
-mod analysis;
-mod command;
-mod error;
-mod metrics;
-mod navigator;
-mod ui;
-
-use crate::command::{AnalyzeCommand, Command, SelectFileCommand};
-use crate::error::AppResult;
-use crate::navigator::FileNavigator;
-use crate::ui::TerminalUI;
 use crossterm::event::{self, Event, KeyCode};
 use dirs::home_dir;
+use rust_code_analysis_tui::analysis::{
+    collect_json_files, diff_to_csv, is_archive, read_baseline, read_file_list, read_path,
+    scan_files, write_file_summaries, DirectoryScan, MetricDelta, MetricsSummary, SummaryOptions,
+};
+use rust_code_analysis_tui::analysis_view::AnalysisView;
+use rust_code_analysis_tui::app::{App, Focus, Popup};
+use rust_code_analysis_tui::cli::{Cli, OutputFormat};
+use rust_code_analysis_tui::command::{
+    AnalyzeCommand, Command, CompareFileCommand, CopyPathCommand, CopyRowCommand,
+    CopySummaryCommand, DirectoriesCommand, ExplainMetricCommand, OpenInEditorCommand,
+    SaveSnapshotCommand, ScreenshotCommand, SelectFileCommand,
+};
+use rust_code_analysis_tui::config::Config;
+use rust_code_analysis_tui::error::{AppError, AppResult};
+use rust_code_analysis_tui::format::NumberFormat;
+use rust_code_analysis_tui::health::health_score;
+use rust_code_analysis_tui::hotspots::{collect_functions, top_functions, Hotspot};
+use rust_code_analysis_tui::keymap::{Action, Keymap};
+use rust_code_analysis_tui::logger;
+use rust_code_analysis_tui::navigator::{
+    common_ancestor, complete_path, default_start_dir, expand_tilde, root_name,
+};
+use rust_code_analysis_tui::thresholds::ThresholdField;
+use rust_code_analysis_tui::ui::TerminalUI;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::env;
-use std::path::Path;
-use std::time::Duration;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
 
-fn run_app(path: String) -> AppResult<()> {
-    let mut details = None;
-    let mut analysis = None;
+const EXIT_THRESHOLD_EXCEEDED: i32 = 2;
 
-    let mut navigator = FileNavigator::new(path.as_ref())?;
+/// Runs the TUI until the user quits, returning the directory to print when they quit with
+/// `Q`.
+fn run_app(
+    paths: Vec<PathBuf>,
+    files: Option<Vec<PathBuf>>,
+    cli: &Cli,
+) -> AppResult<Option<PathBuf>> {
+    logger::set_echo(false);
+    let dir = common_ancestor(&paths);
+    let config = Config::load();
+    let options = cli.summary_options(&config);
+    let mut app = App::new(&dir, config, options)?;
+    if let Some(files) = files {
+        app.start_file_list_analysis(&dir, files);
+    } else if paths.len() > 1 || paths.iter().any(|path| is_archive(path)) {
+        app.start_analysis(&paths);
+    }
+    if cli.presentation {
+        app.toggle_presentation();
+    }
     let mut ui = TerminalUI::new()?;
     let mut analyze_cmd = AnalyzeCommand;
     let mut select_cmd = SelectFileCommand;
+    let keymap = Keymap::new(&app.config.keys);
 
-    loop {
-        ui.draw(&navigator, analysis.clone(), details.clone())?;
+    let exit_path = loop {
+        if let Err(err) = app.poll_navigator() {
+            app.error(err.to_string());
+        }
+        app.poll_job();
+        app.recents.visit(&app.navigator.current);
+        ui.draw(&app)?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Down => navigator.next(),
-                    KeyCode::Up => navigator.previous(),
-                    KeyCode::Enter => {
-                        if let Some(path) = navigator.selected() {
-                            let cmd: &mut dyn Command = if path.is_dir() {
+                if key_event.code == KeyCode::Esc {
+                    if let Err(err) = app.escape() {
+                        app.error(err.to_string());
+                    }
+                    continue;
+                }
+                if app.popup.is_some() {
+                    let action = keymap.action(&key_event);
+                    if let Err(err) = handle_popup_key(&mut app, key_event.code, action) {
+                        app.error(err.to_string());
+                    }
+                    continue;
+                }
+                if app.prompt.is_some() {
+                    if let Err(err) = handle_prompt_key(&mut app, key_event.code) {
+                        app.error(err.to_string());
+                    }
+                    continue;
+                }
+                if app.view.searching {
+                    handle_search_key(&mut app, key_event.code);
+                    continue;
+                }
+                let Some(action) = keymap.action(&key_event) else {
+                    continue;
+                };
+                let result = match action {
+                    Action::Quit => break None,
+                    Action::QuitToPath => break Some(app.navigator.current.clone()),
+                    Action::NextFocus if !app.view.presentation => {
+                        app.next_focus();
+                        Ok(())
+                    }
+                    Action::Search => {
+                        app.view.searching = true;
+                        app.view.focus = Focus::Files;
+                        Ok(())
+                    }
+                    Action::GoTo => {
+                        let mut current = app.navigator.current.display().to_string();
+                        if !current.ends_with(std::path::MAIN_SEPARATOR) {
+                            current.push(std::path::MAIN_SEPARATOR);
+                        }
+                        app.prompt = Some(current);
+                        Ok(())
+                    }
+                    Action::FocusAnalysis | Action::FocusFiles | Action::FocusDetails
+                        if !app.view.presentation =>
+                    {
+                        app.focus_pane(match action {
+                            Action::FocusAnalysis => Focus::Analysis,
+                            Action::FocusFiles => Focus::Files,
+                            _ => Focus::Details,
+                        });
+                        Ok(())
+                    }
+                    Action::ResetView => {
+                        app.reset_view();
+                        Ok(())
+                    }
+                    Action::GrowDetails | Action::ShrinkDetails => {
+                        app.view.resize_details(action == Action::GrowDetails);
+                        Ok(())
+                    }
+                    Action::TogglePresentation => {
+                        app.toggle_presentation();
+                        Ok(())
+                    }
+                    Action::Down => {
+                        match (app.view.focus, app.analysis.as_mut(), app.tree.as_mut()) {
+                            (Focus::Analysis, Some(view), _) => view.next(),
+                            (Focus::Details, _, Some(tree)) => tree.next(),
+                            (Focus::Details, _, None) => {
+                                let rows = app.details.as_ref().map_or(0, |d| d.lines.len());
+                                app.view.next_details_row(rows);
+                            }
+                            _ => app.navigator.next(),
+                        }
+                        Ok(())
+                    }
+                    Action::Up => {
+                        match (app.view.focus, app.analysis.as_mut(), app.tree.as_mut()) {
+                            (Focus::Analysis, Some(view), _) => view.previous(),
+                            (Focus::Details, _, Some(tree)) => tree.previous(),
+                            (Focus::Details, _, None) => app.view.previous_details_row(),
+                            _ => app.navigator.previous(),
+                        }
+                        Ok(())
+                    }
+                    Action::Activate | Action::Toggle if app.view.focus == Focus::Details => {
+                        if let Some(tree) = app.tree.as_mut() {
+                            tree.toggle();
+                        } else {
+                            app.show_subdirectory();
+                        }
+                        Ok(())
+                    }
+                    Action::Left | Action::Right if app.view.focus == Focus::Details => {
+                        if let Some(tree) = app.tree.as_mut() {
+                            if action == Action::Right {
+                                tree.expand();
+                            } else {
+                                tree.collapse();
+                            }
+                        }
+                        Ok(())
+                    }
+                    Action::Activate | Action::Toggle if app.view.focus == Focus::Analysis => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            view.toggle();
+                        }
+                        Ok(())
+                    }
+                    Action::Left | Action::Right if app.view.focus == Focus::Analysis => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            if action == Action::Right {
+                                view.scroll_right();
+                            } else {
+                                view.scroll_left();
+                            }
+                        }
+                        Ok(())
+                    }
+                    Action::Left | Action::Parent => app.navigator.parent(),
+                    Action::Browse => {
+                        match app.navigator.selected().filter(|p| p.is_dir()).cloned() {
+                            Some(dir) => app.navigator.descend_into(&dir),
+                            None => Ok(()),
+                        }
+                    }
+                    Action::Reload => app.navigator.refresh(),
+                    Action::ToggleLog => {
+                        app.view.show_log = !app.view.show_log;
+                        Ok(())
+                    }
+                    Action::ToggleHidden => {
+                        app.navigator.toggle_hidden(&app.view.filter);
+                        Ok(())
+                    }
+                    Action::FollowSymlinks => {
+                        app.toggle_follow_symlinks();
+                        Ok(())
+                    }
+                    Action::Bookmark => {
+                        let current = app.navigator.current.clone();
+                        let added = app.bookmarks.toggle(&current);
+                        app.bookmarks.save().map(|()| {
+                            let verb = if added {
+                                "Bookmarked"
+                            } else {
+                                "Removed bookmark"
+                            };
+                            app.info(format!("{} {}", verb, current.display()));
+                        })
+                    }
+                    Action::Bookmarks => {
+                        app.popup = Some(Popup::Bookmarks { selected: 0 });
+                        Ok(())
+                    }
+                    Action::Recents => {
+                        app.popup = Some(Popup::Recents { selected: 0 });
+                        Ok(())
+                    }
+                    Action::NextGroup | Action::PreviousGroup => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            if action == Action::NextGroup {
+                                view.next_group();
+                            } else {
+                                view.previous_group();
+                            }
+                        }
+                        Ok(())
+                    }
+                    Action::NextFlagged | Action::PreviousFlagged => {
+                        let forward = action == Action::NextFlagged;
+                        if let Some(view) = app.analysis.as_mut() {
+                            if view.jump_flagged(&app.config.thresholds, forward) {
+                                app.view.focus = Focus::Analysis;
+                            } else {
+                                app.info("No metric crosses its thresholds, press 't' to set them");
+                            }
+                        }
+                        Ok(())
+                    }
+                    Action::PerFunction => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            view.toggle_per_function();
+                        }
+                        Ok(())
+                    }
+                    Action::Percentages => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            view.toggle_percentages();
+                        }
+                        Ok(())
+                    }
+                    Action::Values => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            let mode = view.cycle_values();
+                            app.info(format!("Showing {}", mode.label()));
+                        }
+                        Ok(())
+                    }
+                    Action::Compact => {
+                        if let Some(view) = app.analysis.as_mut() {
+                            view.compact = !view.compact;
+                        }
+                        Ok(())
+                    }
+                    Action::Thresholds => {
+                        app.popup = Some(Popup::Thresholds {
+                            selected: 0,
+                            field: ThresholdField::default(),
+                        });
+                        Ok(())
+                    }
+                    Action::Snapshot => SaveSnapshotCommand.execute(&mut app),
+                    Action::History => {
+                        app.popup = Some(Popup::History);
+                        Ok(())
+                    }
+                    Action::Hotspots => {
+                        if app.analysis.is_some() {
+                            app.popup = Some(Popup::Hotspots {
+                                metric: cli.top_by,
+                                hotspots: Vec::new(),
+                                selected: 0,
+                                scroll: 0,
+                            });
+                            app.refresh_hotspots();
+                        } else {
+                            app.error("No hotspots yet, analyze a directory first");
+                        }
+                        Ok(())
+                    }
+                    Action::Chart => {
+                        if app.analysis.is_some() {
+                            app.popup = Some(Popup::Chart {
+                                metric: cli.top_by,
+                                files: Vec::new(),
+                            });
+                            app.refresh_hotspots();
+                        } else {
+                            app.error("No chart yet, analyze a directory first");
+                        }
+                        Ok(())
+                    }
+                    Action::CopyPath => CopyPathCommand.execute(&mut app),
+                    Action::CopySummary => CopySummaryCommand.execute(&mut app),
+                    Action::CopyRow => CopyRowCommand.execute(&mut app),
+                    Action::Compare => CompareFileCommand.execute(&mut app),
+                    Action::Explain => ExplainMetricCommand.execute(&mut app),
+                    Action::Directories => DirectoriesCommand.execute(&mut app),
+                    Action::Screenshot => ScreenshotCommand { ui: &ui }.execute(&mut app),
+                    Action::OpenInEditor => OpenInEditorCommand { ui: &mut ui }.execute(&mut app),
+                    Action::Activate => match app.navigator.selected() {
+                        Some(path) => {
+                            let cmd: &mut dyn Command = if path.is_dir() || is_archive(path) {
                                 &mut analyze_cmd
                             } else {
                                 &mut select_cmd
                             };
-                            cmd.execute(&mut navigator, &mut details, &mut analysis)?;
+                            cmd.execute(&mut app)
                         }
-                    }
-                    KeyCode::Esc => details = None,
-                    _ => {}
+                        None => Ok(()),
+                    },
+                    _ => Ok(()),
+                };
+                match result {
+                    Err(err @ AppError::TerminalError(_)) => return Err(err),
+                    Err(err) => app.error(err.to_string()),
+                    Ok(()) => {}
                 }
             }
         }
-    }
+    };
 
     ui.cleanup()?;
+    if let Err(err) = app.recents.save() {
+        eprintln!("Failed to save recent directories: {}", err);
+    }
+    Ok(exit_path)
+}
+
+fn handle_search_key(app: &mut App, code: KeyCode) {
+    let mut query = app.view.filter.clone();
+    match code {
+        KeyCode::Enter | KeyCode::Down | KeyCode::Up => app.view.searching = false,
+        KeyCode::Backspace => {
+            query.pop();
+        }
+        KeyCode::Char(c) => query.push(c),
+        _ => {}
+    }
+    if query != app.view.filter {
+        app.set_filter(query);
+    }
+}
+
+fn handle_prompt_key(app: &mut App, code: KeyCode) -> AppResult<()> {
+    let Some(input) = app.prompt.as_mut() else {
+        return Ok(());
+    };
+    match code {
+        KeyCode::Enter => {
+            let path = expand_tilde(input.trim());
+            app.prompt = None;
+            app.open_dir(&path)?;
+            app.info(format!("Jumped to {}", path.display()));
+        }
+        KeyCode::Tab => *input = complete_path(input),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Char(c) => input.push(c),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Handles a key in the open popup. The key bound to the action that opened a popup also
+/// closes it.
+fn handle_popup_key(app: &mut App, code: KeyCode, action: Option<Action>) -> AppResult<()> {
+    match app.popup {
+        Some(Popup::Bookmarks { .. }) if action == Some(Action::Bookmarks) => app.popup = None,
+        Some(Popup::Recents { .. }) if action == Some(Action::Recents) => app.popup = None,
+        Some(Popup::Hotspots { .. }) if action == Some(Action::Hotspots) => app.popup = None,
+        Some(Popup::Chart { .. }) if action == Some(Action::Chart) => app.popup = None,
+        Some(Popup::Thresholds { .. }) if action == Some(Action::Thresholds) => {
+            app.close_popup()?
+        }
+        Some(Popup::History) if action == Some(Action::History) => app.popup = None,
+        Some(Popup::Bookmarks { .. }) => handle_bookmarks_key(app, code)?,
+        Some(Popup::Recents { .. }) => handle_recents_key(app, code)?,
+        Some(Popup::Hotspots { .. }) => handle_hotspots_key(app, code),
+        Some(Popup::Chart { ref mut metric, .. }) => match code {
+            KeyCode::Tab => {
+                *metric = metric.next();
+                app.refresh_hotspots();
+            }
+            KeyCode::BackTab => {
+                *metric = metric.previous();
+                app.refresh_hotspots();
+            }
+            _ => {}
+        },
+        Some(Popup::Thresholds { .. }) => handle_thresholds_key(app, code)?,
+        Some(Popup::History) | None => {}
+    }
+    Ok(())
+}
+
+fn handle_hotspots_key(app: &mut App, code: KeyCode) {
+    let Some(Popup::Hotspots {
+        metric,
+        hotspots,
+        selected,
+        scroll,
+    }) = app.popup.as_mut()
+    else {
+        return;
+    };
+    let widest = hotspots
+        .iter()
+        .map(|hotspot| hotspot.location().chars().count())
+        .max()
+        .unwrap_or(0);
+    match code {
+        KeyCode::Down if *selected + 1 < hotspots.len() => *selected += 1,
+        KeyCode::Up => *selected = selected.saturating_sub(1),
+        KeyCode::Right if *scroll + 1 < widest => *scroll += 1,
+        KeyCode::Left => *scroll = scroll.saturating_sub(1),
+        KeyCode::Tab => {
+            *metric = metric.next();
+            *selected = 0;
+            app.refresh_hotspots();
+        }
+        KeyCode::BackTab => {
+            *metric = metric.previous();
+            *selected = 0;
+            app.refresh_hotspots();
+        }
+        KeyCode::Enter => {
+            if let Some(hotspot) = hotspots.get(*selected).cloned() {
+                jump_to_hotspot(app, &hotspot);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Closes the hotspots popup and selects the hotspot's source file in the Files pane.
+fn jump_to_hotspot(app: &mut App, hotspot: &Hotspot) {
+    let Some(root) = app.analysis.as_ref().map(|view| view.root.clone()) else {
+        return;
+    };
+    let Some(path) = hotspot.source_path(&root) else {
+        app.error(format!(
+            "Source of {} not found under {} or its parents",
+            hotspot.file,
+            root.display()
+        ));
+        return;
+    };
+    app.popup = None;
+    app.navigator.reveal(&path);
+    app.view.presentation = false;
+    app.view.focus = Focus::Files;
+    app.info(format!(
+        "{} is at {}:{}",
+        hotspot.name,
+        path.display(),
+        hotspot.start_line
+    ));
+}
+
+fn handle_thresholds_key(app: &mut App, code: KeyCode) -> AppResult<()> {
+    let sections = app.threshold_sections();
+    let Some(Popup::Thresholds { selected, field }) = app.popup.as_mut() else {
+        return Ok(());
+    };
+    let Some(section) = sections.get(*selected) else {
+        app.popup = None;
+        return Ok(());
+    };
+    let key = section.key.unwrap_or_default();
+    let thresholds = &mut app.config.thresholds;
+    match code {
+        KeyCode::Down if *selected + 1 < sections.len() => *selected += 1,
+        KeyCode::Up => *selected = selected.saturating_sub(1),
+        KeyCode::Left | KeyCode::Right | KeyCode::Tab => *field = field.toggle(),
+        KeyCode::Char('+') | KeyCode::Char('=') => {
+            thresholds.adjust(key, *field, section.value, true)
+        }
+        KeyCode::Char('-') => thresholds.adjust(key, *field, section.value, false),
+        KeyCode::Char('d') | KeyCode::Delete => thresholds.clear(key, *field),
+        _ => {}
+    }
     Ok(())
 }
 
+fn handle_bookmarks_key(app: &mut App, code: KeyCode) -> AppResult<()> {
+    let Some(Popup::Bookmarks { selected }) = app.popup.as_mut() else {
+        return Ok(());
+    };
+    let len = app.bookmarks.paths.len();
+    match code {
+        KeyCode::Down if *selected + 1 < len => *selected += 1,
+        KeyCode::Up => *selected = selected.saturating_sub(1),
+        KeyCode::Char('d') => {
+            let index = *selected;
+            *selected = index.min(len.saturating_sub(2));
+            app.bookmarks.remove(index);
+            app.bookmarks.save()?;
+        }
+        KeyCode::Enter => {
+            if let Some(path) = app.bookmarks.paths.get(*selected).cloned() {
+                app.popup = None;
+                app.open_dir(&path).map_err(|_| {
+                    AppError::DirReadError(format!(
+                        "{} (bookmark no longer exists)",
+                        path.display()
+                    ))
+                })?;
+                app.info(format!("Jumped to {}", path.display()));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_recents_key(app: &mut App, code: KeyCode) -> AppResult<()> {
+    let Some(Popup::Recents { selected }) = app.popup.as_mut() else {
+        return Ok(());
+    };
+    let len = app.recents.paths.len();
+    match code {
+        KeyCode::Down if *selected + 1 < len => *selected += 1,
+        KeyCode::Up => *selected = selected.saturating_sub(1),
+        KeyCode::Char('d') => {
+            let index = *selected;
+            *selected = index.min(len.saturating_sub(2));
+            app.recents.remove(index);
+        }
+        KeyCode::Enter => {
+            if let Some(path) = app.recents.paths.get(*selected).cloned() {
+                app.popup = None;
+                app.open_dir(&path).map_err(|_| {
+                    AppError::DirReadError(format!("{} (no longer exists)", path.display()))
+                })?;
+                app.info(format!("Jumped to {}", path.display()));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct HeadlessReport<'a> {
+    #[serde(flatten)]
+    summary: &'a MetricsSummary,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    directories: BTreeMap<String, MetricsSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hotspots: Vec<Hotspot>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    diff: Vec<MetricDelta>,
+}
+
+/// Reads the metrics under each of `paths`, or exactly the `files` listed by `--files-from`,
+/// in which case `paths` is just the listed files' common directory.
+fn scan_inputs<'a>(
+    paths: &'a [PathBuf],
+    files: Option<&[PathBuf]>,
+    options: &SummaryOptions,
+) -> AppResult<Vec<(&'a Path, DirectoryScan)>> {
+    let cancel = AtomicBool::new(false);
+    paths
+        .iter()
+        .map(|path| {
+            let scan = match files {
+                Some(files) => scan_files(files, options, &cancel)?,
+                None => read_path(path, options, &cancel)?,
+            };
+            Ok((path.as_path(), scan))
+        })
+        .collect()
+}
+
+fn run_headless(paths: &[PathBuf], files: Option<&[PathBuf]>, cli: &Cli) -> AppResult<i32> {
+    let started = Instant::now();
+    let config = Config::load();
+    let options = cli.summary_options(&config);
+    let root = common_ancestor(paths);
+    let mut data = Vec::new();
+    let mut directories = BTreeMap::new();
+    let mut ignored = 0;
+    for (path, scan) in scan_inputs(paths, files, &options)? {
+        ignored += scan.ignored;
+        if paths.len() > 1 {
+            directories.insert(
+                root_name(&root, path),
+                MetricsSummary::summarize(&scan.data, &options),
+            );
+        }
+        data.extend(scan.data);
+    }
+    if ignored > 0 {
+        eprintln!("Skipped {} files matched by ignore rules", ignored);
+    }
+    let summary = MetricsSummary::summarize(&data, &options);
+    if summary.files() == 0 {
+        eprintln!("No JSON metrics files found under {}", root.display());
+    } else {
+        eprintln!(
+            "Analyzed {} files in {:.1}s",
+            summary.files(),
+            started.elapsed().as_secs_f64()
+        );
+    }
+    for problem in summary.validate() {
+        eprintln!("Warning: {}", problem);
+    }
+    let diff = match &cli.against {
+        Some(baseline) => summary.diff(&read_baseline(baseline)?),
+        None => Vec::new(),
+    };
+    if cli.format == OutputFormat::Csv {
+        print!("{}", diff_to_csv(&diff));
+    } else if cli.format == OutputFormat::Jsonl {
+        write_file_summaries(io::BufWriter::new(io::stdout().lock()), &data, &options)?;
+    } else {
+        let report = HeadlessReport {
+            summary: &summary,
+            directories,
+            hotspots: cli.top.map_or_else(Vec::new, |n| {
+                top_functions(&collect_functions(&data), cli.top_by, n)
+            }),
+            diff,
+        };
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| AppError::AnalysisError(format!("Failed to serialize summary: {}", e)))?;
+        println!("{}", json);
+    }
+
+    let mut failed = false;
+    for condition in &cli.fail_if {
+        if condition.holds(&summary)? {
+            eprintln!(
+                "Condition failed: {} (actual {:.2})",
+                condition,
+                condition.actual(&summary)?
+            );
+            failed = true;
+        }
+    }
+    let code = if failed { EXIT_THRESHOLD_EXCEEDED } else { 0 };
+    if cli.summary_line {
+        eprintln!("{}", summary_line(&summary, &config, code));
+    }
+    Ok(code)
+}
+
+/// The metrics files `scan_inputs` would read, listed without parsing them, and the archives
+/// left out because they can't be listed without reading them.
+fn list_inputs<'a>(
+    paths: &'a [PathBuf],
+    files: Option<&[PathBuf]>,
+    options: &SummaryOptions,
+) -> (Vec<PathBuf>, Vec<&'a Path>) {
+    if let Some(files) = files {
+        return (files.to_vec(), Vec::new());
+    }
+    let mut found = Vec::new();
+    let mut archives = Vec::new();
+    for path in paths {
+        if is_archive(path) {
+            archives.push(path.as_path());
+        } else {
+            found.extend(collect_json_files(path, options));
+        }
+    }
+    (found, archives)
+}
+
+/// Analyzes `paths` `runs` times without printing any summary, then prints the fastest,
+/// median and slowest wall time and the median throughput.
+fn run_bench(
+    paths: &[PathBuf],
+    files: Option<&[PathBuf]>,
+    cli: &Cli,
+    runs: usize,
+) -> AppResult<()> {
+    let options = cli.summary_options(&Config::load());
+    let mut timings = Vec::with_capacity(runs);
+    let mut analyzed = 0;
+    for _ in 0..runs {
+        let started = Instant::now();
+        let data: Vec<_> = scan_inputs(paths, files, &options)?
+            .into_iter()
+            .flat_map(|(_, scan)| scan.data)
+            .collect();
+        analyzed = MetricsSummary::summarize(&data, &options).files();
+        timings.push(started.elapsed());
+    }
+    let (min, median, max) = spread(timings);
+    println!(
+        "{} runs over {} files: min {:.3}s  median {:.3}s  max {:.3}s  {:.0} files/s",
+        runs,
+        analyzed,
+        min.as_secs_f64(),
+        median.as_secs_f64(),
+        max.as_secs_f64(),
+        analyzed as f64 / median.as_secs_f64().max(f64::EPSILON)
+    );
+    Ok(())
+}
+
+/// The fastest, median and slowest of at least one timing; the median of an even number of
+/// timings is the mean of the middle two.
+fn spread(mut timings: Vec<Duration>) -> (Duration, Duration, Duration) {
+    timings.sort();
+    let middle = timings.len() / 2;
+    let median = if timings.len().is_multiple_of(2) {
+        (timings[middle - 1] + timings[middle]) / 2
+    } else {
+        timings[middle]
+    };
+    (timings[0], median, timings[timings.len() - 1])
+}
+
+/// Prints the overall summary as Markdown, for when there is no terminal to draw the TUI on.
+fn run_text(paths: &[PathBuf], files: Option<&[PathBuf]>, cli: &Cli) -> AppResult<()> {
+    let config = Config::load();
+    let options = cli.summary_options(&config);
+    let root = common_ancestor(paths);
+    let data: Vec<_> = scan_inputs(paths, files, &options)?
+        .into_iter()
+        .flat_map(|(_, scan)| scan.data)
+        .collect();
+    let view = AnalysisView::from_data(&root, &data, &options, config.number_format);
+    if view.is_empty() {
+        eprintln!("{}", view.empty_message());
+    } else {
+        print!("{}", view.to_markdown());
+    }
+    Ok(())
+}
+
+/// Lists the metrics files the TUI would start with, without parsing them, prints how many
+/// there are and how much they weigh, and asks whether to go on when there are more than
+/// `threshold`. Returns false when the user declines and an error when no answer can be read.
+fn confirm_scan(
+    paths: &[PathBuf],
+    files: Option<&[PathBuf]>,
+    cli: &Cli,
+    threshold: usize,
+) -> AppResult<bool> {
+    let options = cli.summary_options(&Config::load());
+    let (found, archives) = list_inputs(paths, files, &options);
+    let bytes: u64 = found
+        .iter()
+        .filter_map(|file| fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    let unlisted = match archives.len() {
+        0 => String::new(),
+        1 => " (not counting 1 archive)".to_string(),
+        n => format!(" (not counting {} archives)", n),
+    };
+    eprintln!(
+        "Found {} JSON files, ~{}{}",
+        NumberFormat {
+            grouping: true,
+            ..NumberFormat::default()
+        }
+        .count(found.len()),
+        human_size(bytes),
+        unlisted
+    );
+    if found.len() <= threshold {
+        return Ok(true);
+    }
+    eprint!("That is more than {}, open them anyway? [y/N] ", threshold);
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stdin is closed").into());
+    }
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.0}{}", size, UNITS[unit])
+}
+
+/// A single `RESULT key=value ...` line that is easy to grep out of a build log.
+fn summary_line(summary: &MetricsSummary, config: &Config, code: i32) -> String {
+    let values = summary.values();
+    let mut line = format!("RESULT files={}", summary.files());
+    for (name, key) in [
+        ("sloc", "loc.sloc"),
+        ("functions", "nom.total"),
+        ("cyclomatic_sum", "cyclomatic.sum"),
+        ("cognitive_sum", "cognitive.sum"),
+    ] {
+        if let Some(value) = values.get(key) {
+            line.push_str(&format!(" {}={:.0}", name, value));
+        }
+    }
+    if let Some(health) = health_score(summary, &config.health, &config.thresholds) {
+        line.push_str(&format!(" health={:.0}", health));
+    }
+    line.push_str(&format!(" exit={}", code));
+    line
+}
+
+/// Resolves a path given on the command line to its absolute canonical form, so that every
+/// path shown or navigated afterwards is absolute, or exits with an error.
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|err| {
+        eprintln!("Error: Cannot resolve path '{}': {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = match Cli::parse(env::args_os().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
 
-    let path = if args.len() > 1 {
-        args[1].clone()
+    let files = cli.files_from.as_ref().map(|list| {
+        read_file_list(list)
+            .unwrap_or_else(|err| {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            })
+            .iter()
+            .map(|file| canonical_path(file))
+            .collect::<Vec<_>>()
+    });
+
+    let paths: Vec<PathBuf> = if let Some(files) = &files {
+        vec![common_ancestor(files)]
+    } else if cli.paths.is_empty() {
+        vec![default_start_dir(home_dir())]
     } else {
-        home_dir()
-            .unwrap_or_else(|| Path::new("/tmp").to_path_buf())
-            .to_str()
-            .unwrap()
-            .to_string()
+        cli.paths.clone()
     };
 
-    if !Path::new(&path).exists() {
-        eprintln!("Error: Path '{}' does not exist", path);
+    if let Some(missing) = paths.iter().find(|path| !path.exists()) {
+        eprintln!("Error: Path '{}' does not exist", missing.display());
         std::process::exit(1);
     }
-    if let Err(err) = run_app(path) {
-        eprintln!("Application error: {}", err);
-        std::process::exit(1);
+    let paths: Vec<PathBuf> = paths.iter().map(|path| canonical_path(path)).collect();
+
+    if cli.list_files {
+        let options = cli.summary_options(&Config::load());
+        let (found, archives) = list_inputs(&paths, files.as_deref(), &options);
+        for archive in archives {
+            eprintln!("Skipping archive {}", archive.display());
+        }
+        for file in found {
+            println!("{}", file.display());
+        }
+        return;
+    }
+
+    if let Some(runs) = cli.bench {
+        if let Err(err) = run_bench(&paths, files.as_deref(), &cli, runs) {
+            eprintln!("Application error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if cli.is_headless() {
+        match run_headless(&paths, files.as_deref(), &cli) {
+            Ok(code) => std::process::exit(code),
+            Err(err) => {
+                eprintln!("Application error: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // The TUI is drawn on stderr, so a redirected stdout (as in `$(...)`) is fine, but without
+    // a terminal on stderr the escape codes would end up in a file or pipe.
+    if !io::stderr().is_terminal() {
+        eprintln!("No terminal to draw on, printing the summary instead");
+        eprintln!("Use --json for structured output");
+        if let Err(err) = run_text(&paths, files.as_deref(), &cli) {
+            eprintln!("Application error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(threshold) = cli.confirm_above {
+        match confirm_scan(&paths, files.as_deref(), &cli, threshold) {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("Declined, nothing opened");
+                std::process::exit(1);
+            }
+            Err(err) => {
+                eprintln!("Could not read a confirmation: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match run_app(paths, files, &cli) {
+        Ok(Some(path)) => println!("{}", path.display()),
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("Application error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn spread_of_an_odd_number_of_runs() {
+        let (min, median, max) = spread(ms(&[30, 10, 20]));
+        assert_eq!(
+            (min, median, max),
+            (ms(&[10])[0], ms(&[20])[0], ms(&[30])[0])
+        );
+    }
+
+    #[test]
+    fn spread_of_an_even_number_of_runs_averages_the_middle() {
+        let (min, median, max) = spread(ms(&[40, 10, 30, 20]));
+        assert_eq!(median, Duration::from_millis(25));
+        assert_eq!(
+            (min, max),
+            (Duration::from_millis(10), Duration::from_millis(40))
+        );
+        let single = spread(ms(&[7]));
+        assert_eq!(single.1, Duration::from_millis(7));
     }
 }
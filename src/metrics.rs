@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Metrics {
@@ -14,6 +15,9 @@ pub struct Metrics {
     pub wmc: Option<Wmc>,
     pub npm: Option<Npm>,
     pub npa: Option<Npa>,
+    /// Fields the typed model doesn't cover, kept for custom metrics.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -127,13 +131,23 @@ pub struct Wmc {
     pub total: Option<f64>,
 }
 
+/// Keeps a present `null` apart from an absent field: absent is `None`, `null` is `Some(None)`.
+fn reported<'de, D>(deserializer: D) -> Result<Option<Option<f64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<f64>::deserialize(deserializer).map(Some)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Npm {
     pub classes: Option<f64>,
     pub interfaces: Option<f64>,
     pub class_methods: Option<f64>,
     pub interface_methods: Option<f64>,
+    #[serde(default, deserialize_with = "reported")]
     pub classes_average: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "reported")]
     pub interfaces_average: Option<Option<f64>>,
     pub total: Option<f64>,
     pub total_methods: Option<f64>,
@@ -146,7 +160,9 @@ pub struct Npa {
     pub interfaces: Option<f64>,
     pub class_attributes: Option<f64>,
     pub interface_attributes: Option<f64>,
+    #[serde(default, deserialize_with = "reported")]
     pub classes_average: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "reported")]
     pub interfaces_average: Option<Option<f64>>,
     pub total: Option<f64>,
     pub total_attributes: Option<f64>,
@@ -1,24 +1,305 @@
-use std::{fs, path::PathBuf};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
 
 use crate::error::{AppError, AppResult};
 
+enum Selection {
+    Remembered,
+    Path(PathBuf),
+}
+
+struct PendingLoad {
+    dir: PathBuf,
+    selection: Selection,
+    receiver: Receiver<AppResult<Vec<PathBuf>>>,
+}
+
 pub struct FileNavigator {
+    pub current: PathBuf,
     pub entries: Vec<PathBuf>,
     pub selected_index: usize,
+    pub show_hidden: bool,
+    /// Descend into symlinked directories at their resolved location, so that going back up
+    /// leads to the target's real parent.
+    pub follow_symlinks: bool,
+    all_entries: Vec<PathBuf>,
+    last_selected: HashMap<PathBuf, usize>,
+    pending: Option<PendingLoad>,
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Case-insensitive subsequence match of `query` in `name`, returning the char indices of
+/// the matched characters.
+pub fn fuzzy_match(name: &str, query: &str) -> Option<Vec<usize>> {
+    let mut indices = Vec::with_capacity(query.len());
+    let mut wanted = query.chars().flat_map(char::to_lowercase).peekable();
+    for (index, c) in name.chars().enumerate() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(std::iter::once(next)) {
+            indices.push(index);
+            wanted.next();
+        }
+    }
+    wanted.peek().is_none().then_some(indices)
+}
+
+/// The entry's display name. Non-UTF-8 names are shown lossily, while the entry itself keeps
+/// its real path for navigation and analysis.
+pub fn file_name(path: &Path) -> Cow<'_, str> {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => path.to_string_lossy(),
+    }
+}
+
+/// The deepest directory containing every path; files count as their parent directory.
+pub fn common_ancestor(paths: &[PathBuf]) -> PathBuf {
+    let mut ancestor: Option<PathBuf> = None;
+    for path in paths {
+        let dir = if path.is_dir() {
+            path.as_path()
+        } else {
+            path.parent().unwrap_or(Path::new(""))
+        };
+        ancestor = Some(match ancestor {
+            None => dir.to_path_buf(),
+            Some(ancestor) => ancestor
+                .components()
+                .zip(dir.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    ancestor
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Names one of several analyzed paths by its path below their `common_ancestor`, or in full
+/// when it is the ancestor itself.
+pub fn root_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .ok()
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Resolves `path` to an absolute canonical path, falling back to the lexical absolute path
+/// when it cannot be canonicalized (for example a broken symlink).
+pub fn absolute_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The directory to start in when no path is given: the home directory, or the system's
+/// temporary directory when there is none.
+pub fn default_start_dir(home: Option<PathBuf>) -> PathBuf {
+    home.unwrap_or_else(env::temp_dir)
+}
+
+/// Expands a leading `~` to the home directory.
+pub fn expand_tilde(input: &str) -> PathBuf {
+    expand_tilde_to(input, dirs::home_dir(), std::path::is_separator)
+}
+
+/// Expands a leading `~` to `home`, followed by one of the characters `is_separator` accepts.
+/// The separators are a parameter so that both platforms' are tested on either.
+fn expand_tilde_to(input: &str, home: Option<PathBuf>, is_separator: fn(char) -> bool) -> PathBuf {
+    let rest = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(is_separator) => rest,
+        _ => return PathBuf::from(input),
+    };
+    match home {
+        Some(home) => home.join(rest.trim_start_matches(is_separator)),
+        None => PathBuf::from(input),
+    }
+}
+
+/// Splits a typed path after its last separator, into the parent as typed and the last
+/// component.
+fn split_last_component(input: &str, is_separator: fn(char) -> bool) -> (&str, &str) {
+    input.split_at(input.rfind(is_separator).map_or(0, |i| i + 1))
+}
+
+/// Completes the last component of a typed path to the longest prefix shared by the matching
+/// directories, adding a separator once the match is unique.
+pub fn complete_path(input: &str) -> String {
+    let (parent, prefix) = split_last_component(input, std::path::is_separator);
+    let dir = if parent.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_tilde(parent)
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return input.to_owned();
+    };
+    let matches: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| {
+            name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.'))
+        })
+        .collect();
+    let Some(first) = matches.first() else {
+        return input.to_owned();
+    };
+    let common = matches.iter().skip(1).fold(first.as_str(), |common, name| {
+        let len = common
+            .char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8());
+        &common[..len]
+    });
+    let mut completed = format!("{}{}", parent, common);
+    if matches.len() == 1 {
+        completed.push(std::path::MAIN_SEPARATOR);
+    }
+    completed
 }
 
 impl FileNavigator {
-    pub fn new(path: &str) -> AppResult<Self> {
+    pub fn new(path: &Path) -> AppResult<Self> {
+        let current = path.to_path_buf();
+        if !current.is_dir() {
+            return Err(AppError::DirReadError(path.display().to_string()));
+        }
+        let mut navigator = Self {
+            current: current.clone(),
+            entries: Vec::new(),
+            selected_index: 0,
+            show_hidden: false,
+            follow_symlinks: false,
+            all_entries: Vec::new(),
+            last_selected: HashMap::new(),
+            pending: None,
+        };
+        navigator.load(current, Selection::Remembered);
+        Ok(navigator)
+    }
+
+    fn read_entries(path: &Path) -> AppResult<Vec<PathBuf>> {
         let entries_iter =
-            fs::read_dir(path).map_err(|_| AppError::DirReadError(path.to_owned()))?;
+            fs::read_dir(path).map_err(|_| AppError::DirReadError(path.display().to_string()))?;
         let mut entries: Vec<PathBuf> = entries_iter
             .filter_map(|entry| entry.ok().map(|e| e.path()))
             .collect();
-        entries.sort();
-        Ok(Self {
-            entries,
-            selected_index: 0,
-        })
+        entries.sort_unstable();
+        Ok(entries)
+    }
+
+    fn load(&mut self, dir: PathBuf, selection: Selection) {
+        let (sender, receiver) = mpsc::channel();
+        let target = dir.clone();
+        thread::spawn(move || {
+            let _ = sender.send(Self::read_entries(&target));
+        });
+        self.pending = Some(PendingLoad {
+            dir,
+            selection,
+            receiver,
+        });
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// The directory being loaded, or the one shown when nothing is loading.
+    pub fn destination(&self) -> &Path {
+        self.pending
+            .as_ref()
+            .map_or(&self.current, |pending| &pending.dir)
+    }
+
+    /// Shows a finished directory load with its entries matching `query`. Returns whether it
+    /// moved to another directory, whose entries are shown unfiltered instead, so that the
+    /// caller can drop the filter.
+    pub fn poll(&mut self, query: &str) -> AppResult<bool> {
+        let Some(pending) = &self.pending else {
+            return Ok(false);
+        };
+        let result = match pending.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return Ok(false),
+            Err(TryRecvError::Disconnected) => {
+                Err(AppError::DirReadError(pending.dir.display().to_string()))
+            }
+        };
+        let Some(pending) = self.pending.take() else {
+            return Ok(false);
+        };
+        let entries = result?;
+
+        let moved = pending.dir != self.current;
+        if moved {
+            self.last_selected
+                .insert(self.current.clone(), self.selected_index);
+        }
+        self.current = pending.dir;
+        self.all_entries = entries;
+        self.apply_filter(if moved { "" } else { query });
+        self.selected_index = 0;
+        match pending.selection {
+            Selection::Remembered => {
+                if let Some(&index) = self.last_selected.get(&self.current) {
+                    self.selected_index = index;
+                    self.clamp_selection();
+                }
+            }
+            Selection::Path(path) => self.select_path(&path),
+        }
+        Ok(moved)
+    }
+
+    fn apply_filter(&mut self, query: &str) {
+        self.entries = self
+            .all_entries
+            .iter()
+            .filter(|path| self.show_hidden || !is_hidden(path))
+            .filter(|path| fuzzy_match(&file_name(path), query).is_some())
+            .cloned()
+            .collect();
+        self.clamp_selection();
+    }
+
+    /// Keeps `selected_index` on a valid row, or 0 when there are no entries.
+    fn clamp_selection(&mut self) {
+        self.selected_index = self
+            .selected_index
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn toggle_hidden(&mut self, query: &str) {
+        self.show_hidden = !self.show_hidden;
+        self.filter(query);
+    }
+
+    /// Shows the entries matching `query`, keeping the selected entry if it still matches.
+    pub fn filter(&mut self, query: &str) {
+        let selected = self.selected().cloned();
+        self.apply_filter(query);
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
     }
 
     pub fn next(&mut self) {
@@ -33,7 +314,286 @@ impl FileNavigator {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// True when the directory has entries but all of them are hidden or filtered out.
+    pub fn only_hidden(&self) -> bool {
+        self.entries.is_empty() && !self.all_entries.is_empty()
+    }
+
     pub fn selected(&self) -> Option<&PathBuf> {
         self.entries.get(self.selected_index)
     }
+
+    /// Opens the selected entry if it is a directory.
+    pub fn enter(&mut self) -> AppResult<()> {
+        match self.selected().filter(|p| p.is_dir()).cloned() {
+            Some(dir) => self.descend_into(&dir),
+            None => Ok(()),
+        }
+    }
+
+    /// Makes `dir` the directory being browsed, without analyzing it. A symlinked directory is
+    /// opened at its resolved location when following symlinks.
+    pub fn descend_into(&mut self, dir: &Path) -> AppResult<()> {
+        if !dir.is_dir() {
+            return Err(AppError::DirReadError(dir.display().to_string()));
+        }
+        let dir = if self.follow_symlinks && dir.is_symlink() {
+            absolute_path(dir)
+        } else {
+            dir.to_path_buf()
+        };
+        self.load(dir, Selection::Remembered);
+        Ok(())
+    }
+
+    pub fn parent(&mut self) -> AppResult<()> {
+        if let Some(parent) = self
+            .current
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+        {
+            let child = self.current.clone();
+            self.load(parent, Selection::Path(child));
+        }
+        Ok(())
+    }
+
+    pub fn refresh(&mut self) -> AppResult<()> {
+        let selection = match self.selected() {
+            Some(path) => Selection::Path(path.clone()),
+            None => Selection::Remembered,
+        };
+        self.load(self.current.clone(), selection);
+        Ok(())
+    }
+
+    /// Opens the directory containing `path` with `path` selected.
+    pub fn reveal(&mut self, path: &Path) {
+        if let Some(dir) = path.parent() {
+            self.load(dir.to_path_buf(), Selection::Path(path.to_path_buf()));
+        }
+    }
+
+    pub fn select_path(&mut self, path: &Path) {
+        if let Some(index) = self.entries.iter().position(|p| p == path) {
+            self.selected_index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::MAIN_SEPARATOR;
+
+    #[test]
+    fn default_start_dir_prefers_home() {
+        let home = PathBuf::from("home").join("user");
+        assert_eq!(default_start_dir(Some(home.clone())), home);
+    }
+
+    #[test]
+    fn default_start_dir_falls_back_to_temp_dir() {
+        assert_eq!(default_start_dir(None), env::temp_dir());
+    }
+
+    #[test]
+    fn common_ancestor_of_sibling_paths() {
+        let root = env::temp_dir().join("project");
+        let paths = [root.join("a").join("metrics"), root.join("b")];
+        assert_eq!(common_ancestor(&paths), root);
+    }
+
+    #[test]
+    fn roots_are_named_below_their_common_ancestor() {
+        let root = env::temp_dir().join("project");
+        assert_eq!(
+            root_name(&root, &root.join("a").join("metrics")),
+            format!("a{}metrics", MAIN_SEPARATOR)
+        );
+        assert_eq!(root_name(&root, &root), root.display().to_string());
+    }
+
+    // Drive letters and backslashes are only parsed as such by `std::path` on Windows, so this
+    // checks that platform's components rather than any separator logic of ours.
+    #[cfg(windows)]
+    #[test]
+    fn common_ancestor_accepts_both_separators() {
+        let paths = [
+            PathBuf::from(r"C:\project\a"),
+            PathBuf::from("C:/project/b"),
+        ];
+        assert_eq!(common_ancestor(&paths), PathBuf::from(r"C:\project"));
+    }
+
+    #[test]
+    fn destination_is_known_before_the_directory_loads() {
+        let root = env::temp_dir().join(format!("rca-tui-destination-{}", std::process::id()));
+        let child = root.join("child");
+        fs::create_dir_all(&child).unwrap();
+        let mut navigator = FileNavigator::new(&root).unwrap();
+        assert_eq!(navigator.destination(), root);
+        while navigator.is_loading() {
+            navigator.poll("").unwrap();
+        }
+        navigator.select_path(&child);
+        navigator.enter().unwrap();
+        assert_eq!(navigator.current, root);
+        assert_eq!(navigator.destination(), child);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn descending_browses_a_directory_and_rejects_files() {
+        let root = env::temp_dir().join(format!("rca-tui-descend-{}", std::process::id()));
+        let child = root.join("child");
+        fs::create_dir_all(&child).unwrap();
+        fs::write(root.join("metrics.json"), "{}").unwrap();
+        let mut navigator = FileNavigator::new(&root).unwrap();
+        while navigator.is_loading() {
+            navigator.poll("").unwrap();
+        }
+        assert!(navigator.descend_into(&root.join("metrics.json")).is_err());
+        assert!(!navigator.is_loading());
+        navigator.descend_into(&child).unwrap();
+        while navigator.is_loading() {
+            navigator.poll("").unwrap();
+        }
+        assert_eq!(navigator.current, child);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn filters_apply_in_place_and_are_dropped_by_moving() {
+        let root = env::temp_dir().join(format!("rca-tui-filter-{}", std::process::id()));
+        let child = root.join("child");
+        fs::create_dir_all(&child).unwrap();
+        fs::write(root.join("metrics.json"), "{}").unwrap();
+        fs::write(child.join("other.json"), "{}").unwrap();
+        let mut navigator = FileNavigator::new(&root).unwrap();
+        while navigator.is_loading() {
+            assert!(!navigator.poll("met").unwrap());
+        }
+        assert_eq!(navigator.entries, [root.join("metrics.json")]);
+        navigator.filter("");
+        assert_eq!(navigator.entries.len(), 2);
+        navigator.select_path(&child);
+        navigator.enter().unwrap();
+        let mut moved = false;
+        while navigator.is_loading() {
+            moved |= navigator.poll("met").unwrap();
+        }
+        assert!(moved);
+        assert_eq!(navigator.entries, [child.join("other.json")]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn expand_tilde_with_forward_slash() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        assert_eq!(expand_tilde("~/metrics"), home.join("metrics"));
+        assert_eq!(expand_tilde("~"), home.join(""));
+    }
+
+    fn windows_separator(c: char) -> bool {
+        c == '\\' || c == '/'
+    }
+
+    fn unix_separator(c: char) -> bool {
+        c == '/'
+    }
+
+    #[test]
+    fn expand_tilde_follows_the_platform_separators() {
+        let home = || Some(PathBuf::from("home"));
+        for input in [r"~\metrics", "~/metrics"] {
+            assert_eq!(
+                expand_tilde_to(input, home(), windows_separator),
+                Path::new("home").join("metrics")
+            );
+        }
+        assert_eq!(
+            expand_tilde_to(r"~\metrics", home(), unix_separator),
+            PathBuf::from(r"~\metrics")
+        );
+        assert_eq!(
+            expand_tilde_to("~", None, windows_separator),
+            PathBuf::from("~")
+        );
+    }
+
+    #[test]
+    fn typed_paths_split_at_either_windows_separator() {
+        assert_eq!(
+            split_last_component(r"C:\project/src\ma", windows_separator),
+            (r"C:\project/src\", "ma")
+        );
+        assert_eq!(
+            split_last_component(r"C:\project\ma", unix_separator),
+            ("", r"C:\project\ma")
+        );
+        assert_eq!(
+            split_last_component("/project/ma", unix_separator),
+            ("/project/", "ma")
+        );
+    }
+
+    #[test]
+    fn expand_tilde_leaves_other_paths_alone() {
+        assert_eq!(
+            expand_tilde("~user/metrics"),
+            PathBuf::from("~user/metrics")
+        );
+        assert_eq!(expand_tilde("metrics/~"), PathBuf::from("metrics/~"));
+    }
+
+    #[test]
+    fn complete_path_uses_the_platform_separator() {
+        let root = env::temp_dir().join(format!("rca-tui-complete-{}", std::process::id()));
+        fs::create_dir_all(root.join("metrics-old")).unwrap();
+        fs::create_dir_all(root.join("metrics-new")).unwrap();
+        fs::create_dir_all(root.join("reports")).unwrap();
+        let prefix = format!("{}{}", root.display(), MAIN_SEPARATOR);
+
+        assert_eq!(
+            complete_path(&format!("{}met", prefix)),
+            format!("{}metrics-", prefix)
+        );
+        assert_eq!(
+            complete_path(&format!("{}rep", prefix)),
+            format!("{}reports{}", prefix, MAIN_SEPARATOR)
+        );
+        assert_eq!(
+            complete_path(&format!("{}missing", prefix)),
+            format!("{}missing", prefix)
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_name_of_non_utf8_path_is_lossy() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let path = Path::new(OsStr::from_bytes(b"dir/metrics-\xff.json"));
+        assert_eq!(file_name(path), "metrics-\u{fffd}.json");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn common_ancestor_of_non_utf8_paths() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let root = env::temp_dir().join(OsStr::from_bytes(b"rca-tui-\xff"));
+        let paths = [
+            root.join("a").join("metrics.json"),
+            root.join("b").join("c.json"),
+        ];
+        assert_eq!(common_ancestor(&paths), root);
+    }
 }
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::{config_dir, load_json},
+    error::{AppError, AppResult},
+};
+
+const RECENTS_FILE: &str = "recent.json";
+const MAX_RECENTS: usize = 20;
+
+/// Directories browsed recently, most recent first.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Recents {
+    pub paths: Vec<PathBuf>,
+}
+
+impl Recents {
+    fn file() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join(RECENTS_FILE))
+    }
+
+    pub fn load() -> Self {
+        load_json(Self::file(), "recent directories")
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let path = Self::file()
+            .ok_or_else(|| AppError::ConfigError("no config directory available".into()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            AppError::ConfigError(format!("failed to encode recent directories: {}", e))
+        })?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Moves `path` to the front of the list, returning false when it already was there.
+    pub fn visit(&mut self, path: &Path) -> bool {
+        if self.paths.first().is_some_and(|p| p == path) {
+            return false;
+        }
+        self.paths.retain(|p| p != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_RECENTS);
+        true
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.paths.len() {
+            self.paths.remove(index);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem},
+};
+use std::collections::HashSet;
+
+use crate::{
+    format::NumberFormat,
+    metrics::{JsonData, Metrics, Space},
+};
+
+pub struct SpaceNode {
+    pub name: String,
+    pub kind: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Cognitive complexity of the space, shown next to each node.
+    pub headline: Option<f64>,
+    pub children: Vec<SpaceNode>,
+}
+
+/// Label of the headline value, named after the metric [`headline`] reads.
+const HEADLINE_LABEL: &str = "cognitive";
+/// Key of the headline value, which its configured precision is looked up under.
+const HEADLINE_KEY: &str = "cognitive.sum";
+
+fn headline(metrics: &Option<Metrics>) -> Option<f64> {
+    metrics
+        .as_ref()
+        .and_then(|m| m.cognitive.as_ref())
+        .and_then(|c| c.sum)
+}
+
+impl SpaceNode {
+    fn from_space(space: &Space) -> Self {
+        Self {
+            name: space.name.clone(),
+            kind: space.kind.clone(),
+            start_line: space.start_line,
+            end_line: space.end_line,
+            headline: headline(&space.metrics),
+            children: space.spaces.iter().map(Self::from_space).collect(),
+        }
+    }
+
+    fn from_data(data: &JsonData) -> Self {
+        Self {
+            name: data.name.clone(),
+            kind: data.kind.clone(),
+            start_line: data.start_line,
+            end_line: data.end_line,
+            headline: headline(&data.metrics),
+            children: data.spaces.iter().map(Self::from_space).collect(),
+        }
+    }
+}
+
+/// A visible row of the tree: the node, its depth and its index path from the roots.
+pub struct TreeRow<'a> {
+    pub node: &'a SpaceNode,
+    pub depth: usize,
+    pub path: Vec<usize>,
+}
+
+/// An expandable view of the `spaces` hierarchy of one metrics file.
+pub struct SpaceTree {
+    pub roots: Vec<SpaceNode>,
+    expanded: HashSet<Vec<usize>>,
+    pub selected: usize,
+}
+
+impl SpaceTree {
+    pub fn from_data(data: &[JsonData]) -> Self {
+        let roots: Vec<SpaceNode> = data.iter().map(SpaceNode::from_data).collect();
+        // Top-level units start expanded so their functions are visible right away.
+        let expanded = (0..roots.len()).map(|index| vec![index]).collect();
+        Self {
+            roots,
+            expanded,
+            selected: 0,
+        }
+    }
+
+    pub fn rows(&self) -> Vec<TreeRow<'_>> {
+        let mut rows = Vec::new();
+        for (index, root) in self.roots.iter().enumerate() {
+            self.push_rows(root, vec![index], &mut rows);
+        }
+        rows
+    }
+
+    fn push_rows<'a>(&'a self, node: &'a SpaceNode, path: Vec<usize>, rows: &mut Vec<TreeRow<'a>>) {
+        let expanded = self.expanded.contains(&path);
+        rows.push(TreeRow {
+            node,
+            depth: path.len() - 1,
+            path: path.clone(),
+        });
+        if expanded {
+            for (index, child) in node.children.iter().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(index);
+                self.push_rows(child, child_path, rows);
+            }
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.rows().len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn selected_row(&self) -> Option<(Vec<usize>, bool)> {
+        self.rows()
+            .into_iter()
+            .nth(self.selected)
+            .map(|row| (row.path, !row.node.children.is_empty()))
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some((path, true)) = self.selected_row() {
+            if !self.expanded.remove(&path) {
+                self.expanded.insert(path);
+            }
+        }
+    }
+
+    pub fn expand(&mut self) {
+        if let Some((path, true)) = self.selected_row() {
+            self.expanded.insert(path);
+        }
+    }
+
+    /// Collapses the selected node, or moves to its parent when it is already collapsed.
+    pub fn collapse(&mut self) {
+        let Some((path, _)) = self.selected_row() else {
+            return;
+        };
+        if self.expanded.remove(&path) {
+            return;
+        }
+        if path.len() > 1 {
+            let parent = &path[..path.len() - 1];
+            if let Some(index) = self.rows().iter().position(|row| row.path == parent) {
+                self.selected = index;
+            }
+        }
+    }
+
+    /// The selected node as plain text, with its kind, lines and headline value.
+    pub fn selected_line(&self, fmt: &NumberFormat) -> Option<String> {
+        let rows = self.rows();
+        let node = rows.get(self.selected)?.node;
+        Some(format!(
+            "{} [{}] L{}-{}  {}: {}",
+            node.name,
+            node.kind,
+            node.start_line,
+            node.end_line,
+            HEADLINE_LABEL,
+            fmt.metric(Some(HEADLINE_KEY), node.headline)
+        ))
+    }
+
+    pub fn list(&self, fmt: &NumberFormat) -> List<'static> {
+        let items: Vec<ListItem> = self
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let marker = match (
+                    row.node.children.is_empty(),
+                    self.expanded.contains(&row.path),
+                ) {
+                    (true, _) => "  ",
+                    (false, true) => "▾ ",
+                    (false, false) => "▸ ",
+                };
+                let node = row.node;
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{}{}", "  ".repeat(row.depth), marker)),
+                    Span::styled(
+                        node.name.clone(),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" [{}] L{}-{}", node.kind, node.start_line, node.end_line),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(
+                            "  {}: {}",
+                            HEADLINE_LABEL,
+                            fmt.metric(Some(HEADLINE_KEY), node.headline)
+                        ),
+                        Style::default().fg(Color::Green),
+                    ),
+                ]))
+            })
+            .collect();
+        List::new(items).highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+        )
+    }
+}
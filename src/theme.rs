@@ -0,0 +1,83 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, Padding},
+};
+use serde::{Deserialize, Serialize};
+
+/// How the Files, Analysis and Details panes are framed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaneBorders {
+    #[default]
+    All,
+    Rounded,
+    /// Only a line above each pane, carrying its title.
+    Minimal,
+    /// No frame; the title keeps its own row.
+    None,
+}
+
+/// Appearance of the panes and of the selected row in the file and bookmark lists.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Drawn in front of the selected row; may be empty.
+    pub highlight_symbol: String,
+    pub highlight_fg: Option<Color>,
+    pub highlight_bg: Option<Color>,
+    pub highlight_bold: bool,
+    pub borders: PaneBorders,
+    /// Columns left empty on each side inside the panes.
+    pub padding: u16,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            highlight_symbol: ">> ".into(),
+            highlight_fg: None,
+            highlight_bg: Some(Color::Blue),
+            highlight_bold: true,
+            borders: PaneBorders::default(),
+            padding: 0,
+        }
+    }
+}
+
+impl Theme {
+    pub fn highlight_style(&self) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.highlight_fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.highlight_bg {
+            style = style.bg(bg);
+        }
+        if self.highlight_bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        style
+    }
+
+    /// A pane framed as configured; the border, or the title without one, is yellow while the
+    /// pane has focus.
+    pub fn pane_block(&self, title: impl Into<String>, focused: bool) -> Block<'static> {
+        let focus_style = if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        let block = Block::default()
+            .title(title.into())
+            .padding(Padding::horizontal(self.padding));
+        match self.borders {
+            PaneBorders::All => block.borders(Borders::ALL).border_style(focus_style),
+            PaneBorders::Rounded => block
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(focus_style),
+            PaneBorders::Minimal => block.borders(Borders::TOP).border_style(focus_style),
+            PaneBorders::None => block.title_style(focus_style),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use ratatui::{
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    widgets::{Cell, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    analysis::{MiVariant, Section},
+    format::NumberFormat,
+    history::higher_is_worse,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Level::Ok => None,
+            Level::Warning => Some(Color::Yellow),
+            Level::Error => Some(Color::Red),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThresholdField {
+    #[default]
+    Warning,
+    Error,
+}
+
+impl ThresholdField {
+    pub fn toggle(self) -> Self {
+        match self {
+            ThresholdField::Warning => ThresholdField::Error,
+            ThresholdField::Error => ThresholdField::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Threshold {
+    pub warning: Option<f64>,
+    pub error: Option<f64>,
+}
+
+impl Threshold {
+    fn field_mut(&mut self, field: ThresholdField) -> &mut Option<f64> {
+        match field {
+            ThresholdField::Warning => &mut self.warning,
+            ThresholdField::Error => &mut self.error,
+        }
+    }
+}
+
+/// Warning and error limits keyed like `--fail-if` metrics, e.g. `cyclomatic.sum`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Thresholds {
+    limits: BTreeMap<String, Threshold>,
+}
+
+/// A step of roughly a tenth of the value's order of magnitude.
+fn step_for(value: f64) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return 1.0;
+    }
+    10f64.powf(value.abs().log10().floor() - 1.0).max(0.01)
+}
+
+impl Thresholds {
+    /// The limits of `key`. An MI headline falls back to limits set under another variant, the
+    /// way `MiVariant::resolve` moves them.
+    pub fn get(&self, key: &str) -> Threshold {
+        let limits = || {
+            self.limits
+                .iter()
+                .map(|(key, limit)| (key.as_str(), *limit))
+        };
+        match MiVariant::ALL
+            .into_iter()
+            .find(|variant| variant.key() == key)
+        {
+            Some(variant) => variant
+                .resolve(limits())
+                .into_iter()
+                .find(|(resolved, _)| *resolved == key)
+                .map(|(_, limit)| limit),
+            None => self.limits.get(key).copied(),
+        }
+        .unwrap_or_default()
+    }
+
+    pub fn level(&self, key: &str, value: f64) -> Level {
+        let threshold = self.get(key);
+        let crosses = |limit: Option<f64>| {
+            limit.is_some_and(|limit| {
+                if higher_is_worse(key) {
+                    value >= limit
+                } else {
+                    value <= limit
+                }
+            })
+        };
+        if crosses(threshold.error) {
+            Level::Error
+        } else if crosses(threshold.warning) {
+            Level::Warning
+        } else {
+            Level::Ok
+        }
+    }
+
+    /// Raises or lowers a limit, starting from `current` when it is unset.
+    pub fn adjust(&mut self, key: &str, field: ThresholdField, current: Option<f64>, up: bool) {
+        let inherited = self.get(key);
+        let threshold = self.limits.entry(key.to_owned()).or_insert(inherited);
+        let limit = threshold.field_mut(field);
+        *limit = Some(match *limit {
+            Some(value) => {
+                let step = step_for(value);
+                if up {
+                    value + step
+                } else {
+                    value - step
+                }
+            }
+            None => current.filter(|v| v.is_finite()).unwrap_or(0.0),
+        });
+    }
+
+    pub fn clear(&mut self, key: &str, field: ThresholdField) {
+        if let Some(threshold) = self.limits.get_mut(key) {
+            *threshold.field_mut(field) = None;
+            if *threshold == Threshold::default() {
+                self.limits.remove(key);
+            }
+        }
+    }
+}
+
+pub fn create_thresholds_table(
+    sections: &[Section],
+    thresholds: &Thresholds,
+    field: ThresholdField,
+    fmt: &NumberFormat,
+) -> Table<'static> {
+    let editing = Style::default().add_modifier(Modifier::UNDERLINED);
+    let rows: Vec<Row> = sections
+        .iter()
+        .filter_map(|section| section.key.map(|key| (section, key)))
+        .map(|(section, key)| {
+            let threshold = thresholds.get(key);
+            let current_style = match section.value.map(|v| thresholds.level(key, v)) {
+                Some(level) => Style::default().fg(level.color().unwrap_or(Color::Green)),
+                None => Style::default(),
+            };
+            let limit_cell = |limit: Option<f64>, cell_field: ThresholdField| {
+                let text = limit.map_or("-".into(), |v| fmt.metric(Some(key), Some(v)));
+                let cell = Cell::from(text);
+                if cell_field == field {
+                    cell.style(editing)
+                } else {
+                    cell
+                }
+            };
+            Row::new(vec![
+                Cell::from(section.title.clone()).style(Style::default().fg(Color::LightBlue)),
+                Cell::from(fmt.metric(Some(key), section.value)).style(current_style),
+                limit_cell(threshold.warning, ThresholdField::Warning),
+                limit_cell(threshold.error, ThresholdField::Error),
+            ])
+        })
+        .collect();
+
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(40),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(
+        Row::new(vec!["Metric", "Current", "Warning", "Error"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .column_spacing(1)
+}
@@ -1,33 +1,152 @@
+use crate::app::{App, Focus, Popup};
 use crate::error::{AppError, AppResult};
-use crate::navigator::FileNavigator;
+use crate::health::health_score;
+use crate::history::create_history_table;
+use crate::hotspots::{create_hotspots_chart, create_hotspots_table, DEFAULT_TOP};
+use crate::logger;
+use crate::navigator::{file_name, fuzzy_match};
+use crate::theme::Theme;
+use crate::thresholds::create_thresholds_table;
 use crossterm::{
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::widgets::Table;
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, TableState, Tabs, Wrap},
     Terminal,
 };
-use std::io;
+use std::{io, path::PathBuf};
+
+const LOG_PANE_HEIGHT: u16 = 8;
+const HISTORY_RUNS_SHOWN: usize = 5;
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 15;
+const PRESENTATION_COLUMN_SPACING: u16 = 6;
+const HOTSPOTS_KEYS: &str = "Tab: metric, ←/→: scroll, Enter: go to file, Esc: close";
+/// Shown in the Analysis pane until something is analyzed, so a first run explains itself.
+const NO_ANALYSIS_HINT: &str = "No analysis yet. Select a directory or archive in the Files pane \
+    and press Enter to analyze it, or Enter on a metrics file to browse its spaces. \
+    Tab switches panes, g jumps to a path, q quits.";
 
 pub struct TerminalUI {
-    pub terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    pub terminal: Terminal<CrosstermBackend<io::Stderr>>,
     pub list_state: ListState,
+    pub analysis_state: TableState,
+    /// What the last `draw` put on screen, for `screenshot`.
+    last_frame: Buffer,
+}
+
+/// Smallest height of the Files and Details panes: both borders and one row.
+const MIN_PANE_HEIGHT: u16 = 3;
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// A pane block whose title starts with the number key that focuses the pane, framed as the
+/// theme says.
+fn numbered_block(theme: &Theme, pane: Focus, title: &str, focused: bool) -> Block<'static> {
+    theme.pane_block(format!("[{}] {}", pane.number(), title), focused)
+}
+
+/// The Files pane items of the `height` entries from `offset`, with the characters matching
+/// `query` highlighted. Only the visible window is built, so huge directories cost no more per
+/// frame than small ones; `file_list_drawing_time` in the tests measures the difference.
+fn file_items(
+    entries: &[PathBuf],
+    query: &str,
+    offset: usize,
+    height: usize,
+) -> Vec<ListItem<'static>> {
+    let match_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    entries
+        .iter()
+        .skip(offset)
+        .take(height)
+        .map(|path| {
+            let name = file_name(path);
+            let matched = fuzzy_match(&name, query).unwrap_or_default();
+            let spans: Vec<Span> = name
+                .chars()
+                .enumerate()
+                .map(|(index, c)| {
+                    if matched.contains(&index) {
+                        Span::styled(c.to_string(), match_style)
+                    } else {
+                        Span::raw(c.to_string())
+                    }
+                })
+                .collect();
+            ListItem::new(Line::from(spans))
+        })
+        .collect()
+}
+
+/// Height of the Files pane for `percent` of `total`, leaving both panes at least
+/// `MIN_PANE_HEIGHT` rows when there is room.
+fn split_height(total: u16, percent: u16) -> u16 {
+    let height = (total as u32 * percent as u32 / 100) as u16;
+    if total < 2 * MIN_PANE_HEIGHT {
+        return height;
+    }
+    height.clamp(MIN_PANE_HEIGHT, total - MIN_PANE_HEIGHT)
+}
+
+/// The first visible row of a list so that at least `scrolloff` rows stay visible around the
+/// selection; a large `scrolloff` keeps the selection centered.
+fn scrolled_offset(
+    current: usize,
+    selected: usize,
+    len: usize,
+    height: usize,
+    scrolloff: usize,
+) -> usize {
+    if height == 0 {
+        return 0;
+    }
+    let scrolloff = scrolloff.min((height - 1) / 2);
+    let mut offset = current;
+    if selected < offset + scrolloff {
+        offset = selected.saturating_sub(scrolloff);
+    }
+    if selected + scrolloff >= offset + height {
+        offset = selected + scrolloff + 1 - height;
+    }
+    offset.min(len.saturating_sub(height))
 }
 
 impl TerminalUI {
     pub fn new() -> AppResult<Self> {
         crossterm::terminal::enable_raw_mode()
             .map_err(|e| AppError::TerminalError(format!("Failed to enable raw mode: {}", e)))?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen).map_err(|e| {
+        // Drawn on stderr so stdout only carries what `Q` prints, for `$(...)` in shell wrappers.
+        let mut stderr = io::stderr();
+        execute!(stderr, EnterAlternateScreen).map_err(|e| {
             AppError::TerminalError(format!("Failed to enter alternate screen: {}", e))
         })?;
-        let backend = CrosstermBackend::new(stdout);
+        let backend = CrosstermBackend::new(stderr);
         let terminal = Terminal::new(backend)
             .map_err(|e| AppError::TerminalError(format!("Terminal init error: {}", e)))?;
         let mut list_state = ListState::default();
@@ -35,70 +154,438 @@ impl TerminalUI {
         Ok(Self {
             terminal,
             list_state,
+            analysis_state: TableState::default(),
+            last_frame: Buffer::default(),
         })
     }
 
-    pub fn draw(
-        &mut self,
-        navigator: &FileNavigator,
-        analysis: Option<Table>,
-        detail: Option<Table>,
-    ) -> AppResult<()> {
-        self.list_state.select(Some(navigator.selected_index));
-        self.terminal
+    pub fn draw(&mut self, app: &App) -> AppResult<()> {
+        let navigator = &app.navigator;
+        self.list_state
+            .select((!navigator.is_empty()).then_some(navigator.selected_index));
+        let frame = self
+            .terminal
             .draw(|f| {
+                let theme = &app.config.theme;
+                let area = f.area();
+                if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+                    let message = Paragraph::new(format!(
+                        "Terminal too small ({}x{}), resize to at least {}x{}",
+                        area.width, area.height, MIN_WIDTH, MIN_HEIGHT
+                    ))
+                    .alignment(Alignment::Center)
+                    .wrap(Wrap { trim: true })
+                    .style(Style::default().fg(Color::Yellow));
+                    f.render_widget(message, area);
+                    return;
+                }
+
+                let log_height = if app.view.show_log {
+                    LOG_PANE_HEIGHT
+                } else {
+                    0
+                };
+                let outer = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(
+                        [
+                            Constraint::Min(0),
+                            Constraint::Length(log_height),
+                            Constraint::Length(1),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(f.area());
+
                 let chunks = Layout::default()
                     .direction(Direction::Horizontal)
-                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-                    .split(f.area());
+                    .constraints(if app.view.presentation {
+                        [Constraint::Percentage(100), Constraint::Percentage(0)]
+                    } else {
+                        [Constraint::Percentage(50), Constraint::Percentage(50)]
+                    })
+                    .split(outer[0]);
+
+                let analysis_focused = app.view.focus == Focus::Analysis;
+                if let Some(view) = app.analysis.as_ref().filter(|view| view.is_empty()) {
+                    let empty_paragraph = Paragraph::new(view.empty_message())
+                        .style(Style::default().fg(Color::Yellow))
+                        .wrap(Wrap { trim: true })
+                        .block(numbered_block(
+                            theme,
+                            Focus::Analysis,
+                            "Metrics Summary",
+                            analysis_focused,
+                        ));
+                    f.render_widget(empty_paragraph, chunks[0]);
+                } else if let Some(view) = &app.analysis {
+                    let block =
+                        numbered_block(theme, Focus::Analysis, &view.title(), analysis_focused);
+                    let inner = block.inner(chunks[0]);
+                    f.render_widget(block, chunks[0]);
+                    let tabs_height = if view.has_groups() { 2 } else { 0 };
+                    let analysis_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Length(tabs_height),
+                                Constraint::Length(2),
+                                Constraint::Min(0),
+                            ]
+                            .as_ref(),
+                        )
+                        .split(inner);
 
-                if let Some(analysis_table) = analysis {
-                    f.render_widget(analysis_table, chunks[0]);
+                    if view.has_groups() {
+                        let names = view.groups[..view.tabs]
+                            .iter()
+                            .map(|group| group.name.clone());
+                        let tabs = Tabs::new(names)
+                            .select((view.group < view.tabs).then_some(view.group))
+                            .highlight_style(
+                                Style::default()
+                                    .fg(Color::Yellow)
+                                    .add_modifier(Modifier::BOLD),
+                            );
+                        f.render_widget(tabs, analysis_chunks[0]);
+                    }
+
+                    let mut overview = view.overview();
+                    if let Some(score) =
+                        health_score(view.summary(), &app.config.health, &app.config.thresholds)
+                    {
+                        overview.push_str(&format!("  Health: {:.0}/100", score));
+                    }
+                    let overview = Paragraph::new(overview)
+                        .style(Style::default().add_modifier(Modifier::BOLD));
+                    f.render_widget(overview, analysis_chunks[1]);
+
+                    let (mut table, selected_row) = view.table(
+                        analysis_focused,
+                        &app.config.thresholds,
+                        app.history.runs(&view.root),
+                    );
+                    if app.view.presentation {
+                        table = table
+                            .column_spacing(PRESENTATION_COLUMN_SPACING)
+                            .highlight_symbol("▶ ")
+                            .row_highlight_style(
+                                Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+                            );
+                    }
+                    self.analysis_state.select(selected_row);
+                    f.render_stateful_widget(table, analysis_chunks[2], &mut self.analysis_state);
                 } else {
-                    let empty_paragraph = Paragraph::new("No analysis result available.")
-                        .block(Block::default().borders(Borders::ALL).title("Analysis"));
+                    let empty_paragraph = Paragraph::new(NO_ANALYSIS_HINT)
+                        .style(Style::default().fg(Color::DarkGray))
+                        .wrap(Wrap { trim: true })
+                        .block(numbered_block(
+                            theme,
+                            Focus::Analysis,
+                            "Analysis",
+                            analysis_focused,
+                        ));
                     f.render_widget(empty_paragraph, chunks[0]);
                 }
 
-                let right_chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
-                    .split(chunks[1]);
-
-                let items: Vec<ListItem> = navigator
-                    .entries
-                    .iter()
-                    .map(|path| {
-                        let name = path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("Unknown");
-                        ListItem::new(name)
-                    })
-                    .collect();
-                let list = List::new(items)
-                    .block(Block::default().borders(Borders::ALL).title("Files"))
-                    .highlight_style(
-                        Style::default()
-                            .bg(Color::Blue)
-                            .add_modifier(Modifier::BOLD),
-                    )
-                    .highlight_symbol(">> ");
-                f.render_stateful_widget(list, right_chunks[0], &mut self.list_state);
+                if !app.view.presentation {
+                    let files_height = split_height(chunks[1].height, app.view.files_percent);
+                    let right_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Length(files_height), Constraint::Min(0)])
+                        .split(chunks[1]);
 
-                if let Some(detail_table) = detail {
-                    f.render_widget(detail_table, right_chunks[1]);
-                } else {
-                    let empty_paragraph = Paragraph::new("No detail selected.")
-                        .block(Block::default().borders(Borders::ALL).title("Details"));
-                    f.render_widget(empty_paragraph, right_chunks[1]);
+                    let mut files_title = if navigator.is_loading() {
+                        "Files (loading…)".to_owned()
+                    } else {
+                        "Files".to_owned()
+                    };
+                    let filter = &app.view.filter;
+                    if app.view.searching || !filter.is_empty() {
+                        files_title.push_str(&format!(" /{}", filter));
+                        if app.view.searching {
+                            files_title.push('█');
+                        }
+                    }
+                    let files_block = numbered_block(
+                        theme,
+                        Focus::Files,
+                        &files_title,
+                        app.view.focus == Focus::Files,
+                    );
+                    if navigator.is_empty() && !navigator.is_loading() {
+                        let message = if !filter.is_empty() {
+                            format!("No entries match '{}'", filter)
+                        } else if navigator.only_hidden() {
+                            "Empty directory (press '.' to show hidden files)".to_owned()
+                        } else {
+                            "Empty directory".to_owned()
+                        };
+                        let placeholder = Paragraph::new(message)
+                            .style(Style::default().fg(Color::DarkGray))
+                            .block(files_block);
+                        f.render_widget(placeholder, right_chunks[0]);
+                    } else {
+                        let height = files_block.inner(right_chunks[0]).height as usize;
+                        let offset = scrolled_offset(
+                            self.list_state.offset(),
+                            navigator.selected_index,
+                            navigator.entries.len(),
+                            height,
+                            app.config.scrolloff,
+                        );
+                        *self.list_state.offset_mut() = offset;
+                        let items = file_items(&navigator.entries, filter, offset, height);
+                        let list = List::new(items)
+                            .block(files_block)
+                            .highlight_style(app.config.theme.highlight_style())
+                            .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+                        let mut window = ListState::default().with_selected(
+                            self.list_state
+                                .selected()
+                                .map(|selected| selected.saturating_sub(offset)),
+                        );
+                        f.render_stateful_widget(list, right_chunks[0], &mut window);
+                    }
+
+                    if let Some(tree) = &app.tree {
+                        let list = tree.list(&app.config.number_format).block(numbered_block(
+                            theme,
+                            Focus::Details,
+                            "Spaces (←/→: collapse/expand)",
+                            app.view.focus == Focus::Details,
+                        ));
+                        let mut state = ListState::default();
+                        state.select(Some(tree.selected));
+                        f.render_stateful_widget(list, right_chunks[1], &mut state);
+                    } else if let Some(details) = &app.details {
+                        let focused = app.view.focus == Focus::Details;
+                        let table = details.table.clone().block(numbered_block(
+                            theme,
+                            Focus::Details,
+                            &details.title,
+                            focused,
+                        ));
+                        let mut state = TableState::default();
+                        state.select(focused.then_some(app.view.details_row));
+                        f.render_stateful_widget(table, right_chunks[1], &mut state);
+                    } else {
+                        let empty_paragraph = Paragraph::new("No detail selected.")
+                            .block(numbered_block(theme, Focus::Details, "Details", false));
+                        f.render_widget(empty_paragraph, right_chunks[1]);
+                    }
+                }
+
+                let status_bar = match (&app.prompt, &app.status) {
+                    (Some(input), _) => Paragraph::new(format!("Go to: {}█", input))
+                        .style(Style::default().fg(Color::Black).bg(Color::Yellow)),
+                    (None, status) => match status {
+                        Some(status) if status.is_error => Paragraph::new(status.message.as_str())
+                            .style(Style::default().fg(Color::White).bg(Color::Red)),
+                        Some(status) => Paragraph::new(status.message.as_str())
+                            .style(Style::default().fg(Color::Black).bg(Color::Gray)),
+                        None => Paragraph::new(""),
+                    },
+                };
+                f.render_widget(status_bar, outer[2]);
+
+                if app.view.show_log {
+                    let lines: Vec<ListItem> =
+                        logger::recent(outer[1].height.saturating_sub(2) as usize)
+                            .into_iter()
+                            .map(|entry| {
+                                ListItem::new(format!(
+                                    "{} {}",
+                                    entry.timestamp(&app.config.timestamps),
+                                    entry.message
+                                ))
+                            })
+                            .collect();
+                    let log = List::new(lines).block(app.config.theme.pane_block("Log", false));
+                    f.render_widget(log, outer[1]);
+                }
+
+                if let Some(Popup::Bookmarks { selected }) = &app.popup {
+                    let area = centered_rect(60, 50, f.area());
+                    let items: Vec<ListItem> = if app.bookmarks.paths.is_empty() {
+                        vec![ListItem::new("No bookmarks yet, press 'b' to add one.")]
+                    } else {
+                        app.bookmarks
+                            .paths
+                            .iter()
+                            .map(|path| ListItem::new(path.display().to_string()))
+                            .collect()
+                    };
+                    let list = List::new(items)
+                        .block(
+                            app.config
+                                .theme
+                                .pane_block("Bookmarks (Enter: jump, d: delete, Esc: close)", true),
+                        )
+                        .highlight_style(app.config.theme.highlight_style())
+                        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+                    let mut state = ListState::default();
+                    state.select((!app.bookmarks.paths.is_empty()).then_some(*selected));
+                    f.render_widget(Clear, area);
+                    f.render_stateful_widget(list, area, &mut state);
+                }
+
+                if let Some(Popup::Recents { selected }) = &app.popup {
+                    let area = centered_rect(60, 50, f.area());
+                    let items: Vec<ListItem> = if app.recents.paths.is_empty() {
+                        vec![ListItem::new("No recent directories yet.")]
+                    } else {
+                        app.recents
+                            .paths
+                            .iter()
+                            .map(|path| ListItem::new(path.display().to_string()))
+                            .collect()
+                    };
+                    let list = List::new(items)
+                        .block(app.config.theme.pane_block(
+                            "Recent directories (Enter: jump, d: forget, Esc: close)",
+                            true,
+                        ))
+                        .highlight_style(app.config.theme.highlight_style())
+                        .highlight_symbol(app.config.theme.highlight_symbol.as_str());
+                    let mut state = ListState::default();
+                    state.select((!app.recents.paths.is_empty()).then_some(*selected));
+                    f.render_widget(Clear, area);
+                    f.render_stateful_widget(list, area, &mut state);
+                }
+
+                if let Some(Popup::History) = &app.popup {
+                    let area = centered_rect(90, 60, f.area());
+                    f.render_widget(Clear, area);
+                    match &app.analysis {
+                        Some(view) if !app.history.runs(&view.root).is_empty() => {
+                            let title = format!("History of {} (Esc: close)", view.root.display());
+                            let table = create_history_table(
+                                app.history.runs(&view.root),
+                                HISTORY_RUNS_SHOWN,
+                                &app.config.number_format,
+                                &app.config.timestamps,
+                            )
+                            .block(app.config.theme.pane_block(title.as_str(), true));
+                            f.render_widget(table, area);
+                        }
+                        _ => {
+                            let message = Paragraph::new(
+                                "No saved snapshots for this directory, press 's' after analyzing.",
+                            )
+                            .block(app.config.theme.pane_block("History (Esc: close)", true));
+                            f.render_widget(message, area);
+                        }
+                    }
+                }
+
+                if let Some(Popup::Hotspots {
+                    metric,
+                    hotspots,
+                    selected,
+                    scroll,
+                }) = &app.popup
+                {
+                    let area = centered_rect(90, 70, f.area());
+                    f.render_widget(Clear, area);
+                    let title = format!(
+                        "Top {} functions by {} ({})",
+                        DEFAULT_TOP,
+                        metric.label(),
+                        HOTSPOTS_KEYS
+                    );
+                    if hotspots.is_empty() {
+                        let message = Paragraph::new("No functions with this metric were found.")
+                            .block(app.config.theme.pane_block(title.as_str(), true));
+                        f.render_widget(message, area);
+                    } else {
+                        let table = create_hotspots_table(
+                            hotspots,
+                            *metric,
+                            &app.config.number_format,
+                            *scroll,
+                        )
+                        .block(app.config.theme.pane_block(title.as_str(), true));
+                        let mut state = TableState::default();
+                        state.select(Some(*selected));
+                        f.render_stateful_widget(table, area, &mut state);
+                    }
+                }
+
+                if let Some(Popup::Chart { metric, files }) = &app.popup {
+                    let area = centered_rect(90, 70, f.area());
+                    f.render_widget(Clear, area);
+                    let bars = area.height.saturating_sub(2) as usize;
+                    let files = &files[..files.len().min(bars)];
+                    let title = format!(
+                        "Top {} files by {} (Tab: metric, Esc: close)",
+                        files.len(),
+                        metric.label()
+                    );
+                    let block = app.config.theme.pane_block(title.as_str(), true);
+                    if files.is_empty() {
+                        let message =
+                            Paragraph::new("No files with this metric were found.").block(block);
+                        f.render_widget(message, area);
+                    } else {
+                        let chart =
+                            create_hotspots_chart(files, *metric, &app.config.number_format)
+                                .block(block);
+                        f.render_widget(chart, area);
+                    }
+                }
+
+                if let Some(Popup::Thresholds { selected, field }) = &app.popup {
+                    let area = centered_rect(70, 60, f.area());
+                    f.render_widget(Clear, area);
+                    let table = create_thresholds_table(
+                        &app.threshold_sections(),
+                        &app.config.thresholds,
+                        *field,
+                        &app.config.number_format,
+                    )
+                    .block(app.config.theme.pane_block(
+                        "Thresholds (+/-: adjust, ←/→: warning/error, d: clear, Esc: save)",
+                        true,
+                    ));
+                    let mut state = TableState::default();
+                    state.select(Some(*selected));
+                    f.render_stateful_widget(table, area, &mut state);
                 }
             })
             .map_err(|e| AppError::TerminalError(format!("UI draw error: {}", e)))?;
+        self.last_frame.clone_from(frame.buffer);
         Ok(())
     }
 
-    pub fn cleanup(&mut self) -> AppResult<()> {
+    /// The last drawn frame as plain text, one line per screen row with trailing blanks
+    /// trimmed.
+    pub fn screenshot(&self) -> String {
+        let buffer = &self.last_frame;
+        let mut text = String::new();
+        for y in buffer.area.top()..buffer.area.bottom() {
+            let mut line = String::new();
+            let mut covered = 0;
+            for x in buffer.area.left()..buffer.area.right() {
+                // Wide characters cover the cells after them, which hold a blank to skip.
+                if covered > 0 {
+                    covered -= 1;
+                    continue;
+                }
+                let cell = &buffer[(x, y)];
+                if cell.skip {
+                    continue;
+                }
+                line.push_str(cell.symbol());
+                covered = Span::raw(cell.symbol()).width().saturating_sub(1);
+            }
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn suspend(&mut self) -> AppResult<()> {
         crossterm::terminal::disable_raw_mode()
             .map_err(|e| AppError::TerminalError(format!("Failed to disable raw mode: {}", e)))?;
         execute!(self.terminal.backend_mut(), LeaveAlternateScreen).map_err(|e| {
@@ -109,4 +596,72 @@ impl TerminalUI {
             .map_err(|e| AppError::TerminalError(format!("Failed to show cursor: {}", e)))?;
         Ok(())
     }
+
+    pub fn resume(&mut self) -> AppResult<()> {
+        crossterm::terminal::enable_raw_mode()
+            .map_err(|e| AppError::TerminalError(format!("Failed to enable raw mode: {}", e)))?;
+        execute!(self.terminal.backend_mut(), EnterAlternateScreen).map_err(|e| {
+            AppError::TerminalError(format!("Failed to enter alternate screen: {}", e))
+        })?;
+        self.terminal
+            .clear()
+            .map_err(|e| AppError::TerminalError(format!("Failed to clear terminal: {}", e)))?;
+        Ok(())
+    }
+
+    pub fn cleanup(&mut self) -> AppResult<()> {
+        self.suspend()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::widgets::StatefulWidget;
+    use std::time::Instant;
+
+    fn entries(count: usize) -> Vec<PathBuf> {
+        (0..count)
+            .map(|i| PathBuf::from(format!("dir/metrics_{:05}.json", i)))
+            .collect()
+    }
+
+    #[test]
+    fn file_items_cover_only_the_visible_window() {
+        let entries = entries(100);
+        assert_eq!(file_items(&entries, "", 10, 20).len(), 20);
+        assert_eq!(file_items(&entries, "", 95, 20).len(), 5);
+        assert!(file_items(&entries, "", 100, 20).is_empty());
+    }
+
+    /// Compares drawing the Files pane of a 40,000-entry directory with every item built, as
+    /// before, against only the visible window. Run it with
+    /// `cargo test --release file_list_drawing_time -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing harness"]
+    fn file_list_drawing_time() {
+        const FRAMES: u32 = 100;
+        let entries = entries(40_000);
+        let area = Rect::new(0, 0, 80, 40);
+        let height = area.height as usize;
+        let offset = entries.len() / 2;
+        let time = |window: usize, offset: usize| {
+            let mut buffer = Buffer::empty(area);
+            let started = Instant::now();
+            for _ in 0..FRAMES {
+                let list = List::new(file_items(&entries, "met", offset, window));
+                let mut state =
+                    ListState::default().with_selected(Some(entries.len() / 2 - offset));
+                StatefulWidget::render(list, area, &mut buffer, &mut state);
+            }
+            started.elapsed().as_secs_f64() * 1000.0 / f64::from(FRAMES)
+        };
+        let all = time(entries.len(), 0);
+        let visible = time(height, offset);
+        println!(
+            "every item: {:.1} ms/frame, visible window: {:.2} ms/frame",
+            all, visible
+        );
+        assert!(visible < all);
+    }
 }